@@ -1,5 +1,8 @@
 use clap::Parser;
-use language_server::infrastructure::runtime;
+use language_server::{
+    adapters::controllers::lsp::{Backend, QUERY_METHOD},
+    infrastructure::runtime,
+};
 use tower_lsp_server::{LspService, Server};
 
 /// Simple program to greet a person
@@ -14,6 +17,8 @@ async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(runtime::init_server);
+    let (service, socket) = LspService::build(runtime::init_server)
+        .custom_method(QUERY_METHOD, Backend::query)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -4,16 +4,63 @@ use hex_color::HexColor;
 use serde::Deserialize;
 use serde_json::Value;
 
+use crate::{
+    entities::{Color, Colors, Severity},
+    use_cases::default_template,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserSeverity {
+    Error,
+    Warning,
+    #[serde(alias = "info")]
+    Information,
+    Hint,
+}
+
+impl From<UserSeverity> for Severity {
+    fn from(value: UserSeverity) -> Self {
+        match value {
+            UserSeverity::Error => Self::Error,
+            UserSeverity::Warning => Self::Warning,
+            UserSeverity::Information => Self::Information,
+            UserSeverity::Hint => Self::Hint,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct UserColors {
     #[serde(default = "UserColors::default_background")]
     pub background: HexColor,
+    /// Text color of the keyword, rendered through semantic tokens
+    #[serde(default)]
+    pub foreground: Option<HexColor>,
+    /// Severity of the diagnostic published for every match of the keyword
+    #[serde(default = "UserColors::default_severity")]
+    pub severity: UserSeverity,
+    /// Whether matches of the keyword are published as diagnostics at all
+    #[serde(default = "UserColors::default_diagnostics")]
+    pub diagnostics: bool,
+    /// Free text explaining what the keyword stands for, shown on hover
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Snippet inserted when completing the keyword inside a comment,
+    /// `$USER` and `$DATE` are replaced by the server
+    #[serde(default)]
+    pub template: Option<String>,
 }
 
 impl Default for UserColors {
     fn default() -> Self {
         Self {
             background: UserColors::default_background(),
+            foreground: None,
+            severity: UserColors::default_severity(),
+            diagnostics: UserColors::default_diagnostics(),
+            description: None,
+            template: None,
         }
     }
 }
@@ -22,18 +69,48 @@ impl UserColors {
     fn default_background() -> HexColor {
         HexColor::rgba(134, 134, 134, 255)
     }
+
+    fn default_severity() -> UserSeverity {
+        UserSeverity::Hint
+    }
+
+    fn default_diagnostics() -> bool {
+        true
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl From<&UserColors> for Colors {
+    fn from(value: &UserColors) -> Self {
+        let to_color = |hex: HexColor| Color::new(hex.r, hex.g, hex.b, hex.a);
+
+        Colors::builder()
+            .background(to_color(value.background))
+            .foreground(value.foreground.map(to_color))
+            .build()
+    }
+}
+
+/// Turns the ticket references matching `regex` into links to `url`, where
+/// `$1`, `$2`... are replaced by the capture groups of the regex
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TicketPattern {
+    pub regex: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     #[serde(deserialize_with = "Config::desierialize_highlights")]
     pub highlights: HashMap<String, UserColors>,
+    #[serde(default)]
+    pub ticket_patterns: Vec<TicketPattern>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             highlights: Self::default_highlights(),
+            ticket_patterns: vec![],
         }
     }
 }
@@ -59,7 +136,35 @@ impl Config {
     }
 
     pub fn parse_json(v: Value) -> Self {
-        serde_json::from_value(v).unwrap_or_default()
+        Self::try_parse_json(v).unwrap_or_default()
+    }
+
+    /// Parses the configuration, `None` if it does not define the highlights
+    pub fn try_parse_json(v: Value) -> Option<Self> {
+        serde_json::from_value(v).ok()
+    }
+
+    /// Severity of the diagnostics reported for `keyword`, `None` when the
+    /// keyword is unknown or the user opted out of its diagnostics
+    pub fn severity(&self, keyword: &str) -> Option<Severity> {
+        self.highlights
+            .get(keyword)
+            .filter(|user_colors| user_colors.diagnostics)
+            .map(|user_colors| user_colors.severity.into())
+    }
+
+    pub fn description(&self, keyword: &str) -> Option<&str> {
+        self.highlights
+            .get(keyword)
+            .and_then(|user_colors| user_colors.description.as_deref())
+    }
+
+    /// Completion snippet of `keyword`, falling back to the default template
+    pub fn template(&self, keyword: &str) -> String {
+        self.highlights
+            .get(keyword)
+            .and_then(|user_colors| user_colors.template.clone())
+            .unwrap_or_else(|| default_template(keyword))
     }
 }
 
@@ -78,6 +183,60 @@ mod tests {
         assert_eq!(colors.background.split_rgb(), (129, 129, 129));
     }
 
+    #[test]
+    fn parse_ticket_patterns_config_works() {
+        let raw_json = json!({
+            "highlights": {},
+            "ticket_patterns": [
+                {"regex": "PROJ-(\\d+)", "url": "https://tracker/browse/PROJ-$1"},
+            ],
+        });
+        let config = Config::parse_json(raw_json);
+
+        assert_eq!(
+            config.ticket_patterns,
+            vec![TicketPattern {
+                regex: r"PROJ-(\d+)".to_owned(),
+                url: "https://tracker/browse/PROJ-$1".to_owned(),
+            }]
+        );
+        assert!(
+            Config::parse_json(json!({"highlights": {}}))
+                .ticket_patterns
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn parse_template_config_works() {
+        let raw_json = json!({
+            "highlights": {
+                "TODO": {},
+                "FIXME": {
+                    "template": "FIXME(#${1:ticket}): $0",
+                },
+            }
+        });
+        let config = Config::parse_json(raw_json);
+
+        assert_eq!(config.template("TODO"), "TODO(${1:$USER}): $0");
+        assert_eq!(config.template("FIXME"), "FIXME(#${1:ticket}): $0");
+    }
+
+    #[test]
+    fn parse_foreground_config_works() {
+        let raw_json = json!({"foreground": "#ff0000"});
+        let user_colors = serde_json::from_value::<UserColors>(raw_json).unwrap();
+        let colors = Colors::from(&user_colors);
+
+        assert_eq!(user_colors.background, UserColors::default_background());
+        assert_eq!(
+            colors.foreground.map(Color::into_components),
+            Some((255, 0, 0, 255))
+        );
+        assert!(Colors::from(&UserColors::default()).foreground.is_none());
+    }
+
     #[test]
     fn parse_null_config_works() {
         let raw_json = json!(null);
@@ -104,6 +263,13 @@ mod tests {
         assert_eq!(config.highlights.len(), 1);
     }
 
+    #[test]
+    fn try_parse_requires_highlights() {
+        assert!(Config::try_parse_json(json!(null)).is_none());
+        assert!(Config::try_parse_json(json!({})).is_none());
+        assert!(Config::try_parse_json(json!({"highlights": {}})).is_some());
+    }
+
     #[test]
     fn parse_keyword_empty_config_works() {
         let raw_json = json!({
@@ -153,5 +319,50 @@ mod tests {
         assert_eq!(highlights["FIXME"].background.split_rgb(), (184, 184, 14));
         assert_eq!(highlights["BUG"].background.split_rgb(), (255, 0, 0));
     }
+
+    #[test]
+    fn parse_severity_config_works() {
+        let raw_json = json!({
+            "highlights": {
+                "TODO": {},
+                "FIXME": {
+                    "severity": "warning",
+                },
+                "BUG": {
+                    "severity": "error",
+                },
+                "NOTE": {
+                    "severity": "info",
+                    "diagnostics": false,
+                },
+            }
+        });
+        let config = Config::parse_json(raw_json);
+
+        assert_eq!(config.severity("TODO"), Some(Severity::Hint));
+        assert_eq!(config.severity("FIXME"), Some(Severity::Warning));
+        assert_eq!(config.severity("BUG"), Some(Severity::Error));
+        assert_eq!(config.severity("NOTE"), None);
+        assert_eq!(config.severity("HACK"), None);
+    }
+
+    #[test]
+    fn parse_description_config_works() {
+        let raw_json = json!({
+            "highlights": {
+                "TODO": {},
+                "HACK": {
+                    "description": "Works, but should be done properly",
+                },
+            }
+        });
+        let config = Config::parse_json(raw_json);
+
+        assert_eq!(config.description("TODO"), None);
+        assert_eq!(
+            config.description("HACK"),
+            Some("Works, but should be done properly")
+        );
+    }
 }
 // grcov-excl-stop
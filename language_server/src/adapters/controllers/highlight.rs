@@ -37,16 +37,13 @@ impl<T: Colorer> Highlight<T> {
 
     pub fn update_palette(&mut self, palette: HashMap<String, UserColors>) {
         palette.into_iter().for_each(|(key, user_colors)| {
-            let UserColors { background } = user_colors;
-            let colors = Colors::builder()
-                .background(Color::new(
-                    background.r,
-                    background.g,
-                    background.b,
-                    background.a,
-                ))
-                .build();
-            self.inner.update_palette(key, colors);
+            self.inner.update_palette(key, Colors::from(&user_colors));
         });
     }
+
+    /// Drops the keywords that are not part of `palette` anymore
+    pub fn replace_palette(&mut self, palette: HashMap<String, UserColors>) {
+        self.inner.clear_palette();
+        self.update_palette(palette);
+    }
 }
@@ -1,46 +1,543 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    env, fs,
+    ops::RangeInclusive,
+    path::{Component, Path},
+    sync::Arc,
+};
 
+use serde_json::{Value, json};
 use tokio::sync::RwLock;
 use tower_lsp_server::{
     Client, LanguageServer,
-    jsonrpc::{Error, Result},
+    jsonrpc::{Error, ErrorCode, Result},
     ls_types::{
-        ColorInformation, ColorProviderCapability, DidChangeTextDocumentParams,
-        DidOpenTextDocumentParams, DocumentColorParams, InitializeParams, InitializeResult,
-        InitializedParams, MessageType, OneOf, Range, ServerCapabilities, ServerInfo,
-        TextDocumentItem, TextDocumentSyncCapability, TextDocumentSyncKind,
-        TextDocumentSyncOptions, VersionedTextDocumentIdentifier,
-        WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
+        self, ClientCapabilities, CodeActionParams, CodeActionProviderCapability,
+        CodeActionResponse, CodeLens, CodeLensOptions, CodeLensParams, ColorInformation,
+        ColorProviderCapability, Command, CompletionItem, CompletionItemKind, CompletionOptions,
+        CompletionParams, CompletionResponse, ConfigurationItem, CreateFilesParams,
+        DeleteFilesParams, DiagnosticOptions, DiagnosticServerCapabilities,
+        DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+        DidChangeWatchedFilesRegistrationOptions, DidChangeWorkspaceFoldersParams,
+        DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+        DocumentColorParams, DocumentDiagnosticParams, DocumentDiagnosticReport,
+        DocumentDiagnosticReportResult, DocumentHighlight, DocumentHighlightKind,
+        DocumentHighlightParams, DocumentLink, DocumentLinkOptions, DocumentLinkParams,
+        DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandOptions, ExecuteCommandParams,
+        FileOperationFilter, FileOperationPattern, FileOperationRegistrationOptions,
+        FileSystemWatcher, FoldingRange, FoldingRangeParams, FoldingRangeProviderCapability,
+        FullDocumentDiagnosticReport, GlobPattern, Hover, HoverContents, HoverParams,
+        HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams,
+        InsertTextFormat, Location, MarkupContent, MarkupKind, MessageType, OneOf,
+        PrepareRenameResponse, ProgressToken, Range, ReferenceParams, Registration,
+        RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport,
+        RenameFilesParams, RenameOptions, RenameParams, SemanticTokens, SemanticTokensParams,
+        SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensRegistrationOptions,
+        SemanticTokensResult, ServerCapabilities, ServerInfo, StaticRegistrationOptions,
+        SymbolKind, TextDocumentItem, TextDocumentPositionParams, TextDocumentRegistrationOptions,
+        TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+        TextDocumentSyncSaveOptions, UnchangedDocumentDiagnosticReport, Unregistration, Uri,
+        VersionedTextDocumentIdentifier, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+        WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport, WorkspaceEdit,
+        WorkspaceFileOperationsServerCapabilities, WorkspaceFolder, WorkspaceFoldersChangeEvent,
+        WorkspaceFoldersServerCapabilities, WorkspaceFullDocumentDiagnosticReport,
+        WorkspaceServerCapabilities, WorkspaceSymbol, WorkspaceSymbolParams,
+        WorkspaceSymbolResponse, WorkspaceUnchangedDocumentDiagnosticReport,
     },
 };
 
 use crate::{
     adapters::{
         config::Config,
+        controllers::query::{QueryParams, QueryResponse},
         controllers::{highlight::Highlight, search::Search},
-        gateways::{color_provider::ColorProvider, ripgrep::RipGrepSearcher},
-        presenters::{ColorPresenter, PositionPresenter},
+        gateways::{color_provider::ColorProvider, ripgrep::RipGrepSearcher, tickets::TicketLinks},
+        presenters::{
+            ColorPresenter, DIAGNOSTIC_SOURCE, Encoder, MarkdownReportPresenter, PositionEncoding,
+            PositionPresenter, ReportEntry, Target, code_actions, diagnostics, document_links,
+            document_symbols, folding_ranges, hover_contents, keyword_summary, mention_ranges,
+            overlaps, rename_edits, result_id, semantic_tokens, semantic_tokens_options, target_at,
+            target_ranges,
+        },
+    },
+    entities::{
+        Color, ColorType, Colors, Column, Document, FileState, Position, Row, State, TodoResult,
+        WideChars,
+    },
+    use_cases::{
+        expand_template, fuzzy_score, is_in_comment,
+        ports::{Colorer, Conversion, RegexSearcher, Searcher},
+        today, user_name,
     },
-    entities::{Color, ColorType, Colors, Position, State, TodoResult},
-    use_cases::ports::{Colorer, Conversion, RegexSearcher, Searcher},
 };
 
+const CONFIG_SECTION: &str = "todo-highlight-lsp";
+const LIST_FILE_COMMAND: &str = "todo.listFile";
+const RESCAN_WORKSPACE_COMMAND: &str = "todo.rescanWorkspace";
+const LIST_ALL_COMMAND: &str = "todo.listAll";
+const TOGGLE_HIGHLIGHTING_COMMAND: &str = "todo.toggleHighlighting";
+const EXPORT_REPORT_COMMAND: &str = "todo.exportReport";
+const WATCHED_FILES_REGISTRATION: &str = "todo-highlight-watched-files";
+/// Id of the dynamic registration of the semantic tokens, replaced whenever
+/// their legend changes
+const SEMANTIC_TOKENS_REGISTRATION: &str = "todo-highlight-semantic-tokens";
+const SEMANTIC_TOKENS_METHOD: &str = "textDocument/semanticTokens";
+const SCAN_PROGRESS_TOKEN: &str = "todo-highlight-scan";
+/// Custom request listing the matches of the workspace, see [`Backend::query`]
+pub const QUERY_METHOD: &str = "todo/query";
+
 struct Protected<S, G, H> {
     state: S,
-    grep: G,
+    scanner: Scanner<G>,
     highlighter: H,
+    /// Ticket links of the server's configuration
+    tickets: TicketLinks,
+    /// Incremented by every scan of the whole workspace, a scan in progress
+    /// dropping its results once a later one started
+    scan_generation: usize,
+    /// Configuration of the initialization options, applied again when the
+    /// settings no longer define one
+    init_config: Config,
+    /// Text of the documents opened in the editor
+    documents: HashMap<String, Document>,
+    /// Capabilities announced by the client on initialization
+    capabilities: ClientCapabilities,
+    /// Whether keywords are colored, toggled by a command
+    highlighting: bool,
+    /// Unit in which the client counts the characters of positions
+    encoding: PositionEncoding,
+    /// Keywords emitted as semantic tokens, the index of a keyword being its
+    /// token type in the legend advertised on initialization
+    token_types: Vec<String>,
 }
 
 impl<S, G, H> Protected<S, G, H> {
-    pub fn new(state: S, grep: G, highlighter: H) -> Arc<RwLock<Self>> {
+    pub fn new(state: S, grep: G, highlighter: H, config: Config) -> Arc<RwLock<Self>> {
+        let tickets = TicketLinks::try_new(&config.ticket_patterns).unwrap_or_default();
+        let init_config = config.clone();
+
         Arc::new(RwLock::new(Self {
             state,
-            grep,
+            scanner: Scanner {
+                grep,
+                config,
+                folders: vec![],
+            },
             highlighter,
+            tickets,
+            scan_generation: 0,
+            init_config,
+            documents: HashMap::new(),
+            capabilities: ClientCapabilities::default(),
+            highlighting: true,
+            encoding: PositionEncoding::default(),
+            token_types: vec![],
         }))
     }
 }
 
+impl<S, C> Protected<State, Search<S>, Highlight<C>>
+where
+    S: Searcher,
+    C: Colorer,
+{
+    /// Ticket links applying to `file_name`: the ones of its folder's
+    /// configuration, if the folder has its own, otherwise the server's ones
+    fn tickets_for(&self, file_name: &str) -> &TicketLinks {
+        self.scanner
+            .folder_of(file_name)
+            .and_then(|folder| folder.tickets.as_ref())
+            .unwrap_or(&self.tickets)
+    }
+
+    /// The server's configuration and the ones of the folders having their own
+    fn configs(&self) -> impl Iterator<Item = &Config> {
+        self.scanner
+            .folders
+            .iter()
+            .filter_map(|folder| folder.config.as_ref())
+            .chain([&self.scanner.config])
+    }
+
+    /// Every keyword searched for, across the server's and folders' configs
+    fn keywords(&self) -> Vec<String> {
+        let mut keywords = self
+            .configs()
+            .flat_map(|config| config.highlights.keys().cloned())
+            .collect::<Vec<_>>();
+        keywords.sort();
+        keywords.dedup();
+
+        keywords
+    }
+
+    /// Keywords with a foreground color in any of the configs, sorted to keep
+    /// the legend of the semantic tokens stable
+    fn foreground_keywords(&self) -> Vec<String> {
+        let mut keywords = self
+            .configs()
+            .flat_map(|config| &config.highlights)
+            .filter(|(_, user_colors)| Colors::from(*user_colors).foreground.is_some())
+            .map(|(keyword, _)| keyword.clone())
+            .collect::<Vec<_>>();
+        keywords.sort();
+        keywords.dedup();
+
+        keywords
+    }
+
+    /// Whether the client lets the semantic tokens be registered dynamically,
+    /// so that their legend follows the configuration
+    fn registers_semantic_tokens(&self) -> bool {
+        self.capabilities
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.semantic_tokens.as_ref())
+            .and_then(|semantic_tokens| semantic_tokens.dynamic_registration)
+            .unwrap_or(false)
+    }
+
+    fn color(&self, file_name: &str, keyword: &str, color_type: ColorType) -> Option<Color> {
+        match self
+            .scanner
+            .folder_of(file_name)
+            .and_then(|f| f.config.as_ref())
+        {
+            Some(config) => config.highlights.get(keyword).and_then(|user_colors| {
+                let colors = Colors::from(user_colors);
+                match color_type {
+                    ColorType::Background => Some(colors.background),
+                    ColorType::Foreground => colors.foreground,
+                }
+            }),
+            None => self.highlighter.highlight(keyword, color_type),
+        }
+    }
+
+    fn search_in_text(&self, file_name: &str, text: &str) -> Option<FileState> {
+        let config = self.scanner.config_for(file_name);
+        let mut file_state = self.scanner.grep.search_in_text(text)?;
+        file_state.retain(|m| config.highlights.contains_key(m.keyword()));
+
+        (!file_state.is_empty()).then_some(file_state)
+    }
+
+    /// Replaces the whole text of an open document
+    fn set_document(&mut self, file_name: &str, text: &str) {
+        let file_state = self.search_in_text(file_name, text);
+        self.state.overlay(file_name.to_owned(), file_state);
+        self.documents
+            .insert(file_name.to_owned(), Document::new(text));
+    }
+
+    /// Applies a range edit to an open document and searches again only the
+    /// rows written by the edit
+    fn edit_document(&mut self, file_name: &str, range: Range, text: &str) {
+        let encoding = self.encoding;
+        let Some(document) = self.documents.get_mut(file_name) else {
+            return;
+        };
+        let start = document_position(encoding, document, range.start);
+        let end = document_position(encoding, document, range.end);
+        let (removed, inserted) = document.edit(start, end, text);
+
+        // A comment continued up to the edited rows is searched again, since
+        // the edit may extend or cut it
+        let first_row = self
+            .state
+            .get(file_name)
+            .and_then(|file_state| file_state.rows().range(..removed.start).next_back())
+            .filter(|(row, (_, matches))| {
+                matches
+                    .last()
+                    .is_some_and(|m| row.row() + m.continuation() + 1 >= removed.start.row())
+            })
+            .map_or(removed.start, |(row, _)| *row);
+        let count = removed.start.row() - first_row.row() + inserted;
+        let following = first_row..usize::MAX.into();
+        let config = self.scanner.config_for(file_name);
+        let mut rows = self.scanner.grep.search_in_lines(
+            first_row,
+            count,
+            self.documents[file_name].lines(following),
+        );
+        rows.retain(|_, (_, matches)| {
+            matches.retain(|m| config.highlights.contains_key(m.keyword()));
+            !matches.is_empty()
+        });
+
+        self.state.splice(file_name, removed, inserted, rows);
+    }
+
+    /// Closes an open document, its matches being read again from disk since
+    /// the closed buffer may hold discarded edits
+    fn close_document(&mut self, file_name: &str) {
+        let _ = self.documents.remove(file_name);
+        self.state.close(file_name);
+        let _ = self.reload_from_disk(file_name);
+    }
+
+    /// Moves a renamed file or directory, open documents included. Returns
+    /// the previous names of the moved files.
+    fn rename_file(&mut self, from: &str, to: &str) -> Vec<String> {
+        let moved = self.state.rename(from, to);
+        for (old_name, new_name) in &moved {
+            if let Some(document) = self.documents.remove(old_name) {
+                self.documents.insert(new_name.clone(), document);
+            }
+        }
+
+        // The new location may be skipped or use other keywords
+        let mut purged = self.reload_from_disk(to);
+        purged.extend(moved.into_iter().map(|(old_name, _)| old_name));
+
+        purged
+    }
+
+    /// Counts the columns of `file_state` in the negotiated encoding
+    fn encoder<'a>(&self, file_state: &'a FileState) -> Encoder<'a> {
+        Encoder::new(self.encoding, file_state)
+    }
+
+    /// Row and byte column of a position sent by the client, read from the
+    /// open document or else from the rows with matches
+    fn row_column(&self, file_name: &str, position: ls_types::Position) -> (Row, Column) {
+        if let Some(document) = self.documents.get(file_name) {
+            return document_position(self.encoding, document, position).into_pair();
+        }
+
+        let row = Row::from(position.line as usize);
+        let character = Column::from(position.character as usize);
+        let column = self
+            .state
+            .get(file_name)
+            .and_then(|file_state| file_state.metadata(row))
+            .map_or(character, |metadata| {
+                self.encoding.decode(character, metadata.wide_chars())
+            });
+
+        (row, column)
+    }
+
+    /// Whether the client pulls diagnostics, in which case they are not pushed
+    fn pulls_diagnostics(&self) -> bool {
+        self.capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some())
+    }
+
+    /// Full report of the diagnostics of a file, or an unchanged report when
+    /// they are the ones the client got as `previous_result_id`
+    fn diagnostic_report(
+        &self,
+        file_name: &str,
+        previous_result_id: Option<&str>,
+    ) -> std::result::Result<FullDocumentDiagnosticReport, UnchangedDocumentDiagnosticReport> {
+        let items = self
+            .state
+            .get(file_name)
+            .map(|file_state| {
+                diagnostics(self.scanner.config_for(file_name), self.encoder(file_state))
+            })
+            .unwrap_or_default();
+        let result_id = result_id(&items);
+
+        match previous_result_id == Some(result_id.as_str()) {
+            true => Err(UnchangedDocumentDiagnosticReport { result_id }),
+            false => Ok(FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items,
+            }),
+        }
+    }
+
+    /// Reads again from disk a file or a directory, whatever happened to it.
+    /// Returns the files which no longer have any match.
+    fn reload_from_disk(&mut self, file_name: &str) -> Vec<String> {
+        let path = Path::new(file_name);
+        let mut purged = self
+            .state
+            .retain_files(|other| !Path::new(other).starts_with(path));
+        let scanned = self.scanner.search_on_disk(file_name);
+        self.state.extend(scanned);
+        purged.retain(|other| self.state.get(other).is_none());
+
+        purged
+    }
+
+    /// The files with matches at `file_name` or below it
+    fn files_under(&self, file_name: &str) -> Vec<String> {
+        let path = Path::new(file_name);
+
+        self.state
+            .iter()
+            .filter(|(other, _)| Path::new(other).starts_with(path))
+            .map(|(other, _)| other.to_owned())
+            .collect()
+    }
+}
+
+/// The searcher and the configurations, which a scan of the disk copies so
+/// that it runs without holding the lock on the rest
+#[derive(Clone)]
+struct Scanner<G> {
+    grep: G,
+    config: Config,
+    /// Workspace folders that are scanned
+    folders: Vec<Folder>,
+}
+
+impl<S: Searcher> Scanner<Search<S>> {
+    /// The folder holding `file_name`, the innermost one for nested folders
+    fn folder_of(&self, file_name: &str) -> Option<&Folder> {
+        self.folders
+            .iter()
+            .filter(|folder| Path::new(file_name).starts_with(&folder.root))
+            .max_by_key(|folder| folder.root.len())
+    }
+
+    /// Configuration applying to `file_name`: the one of its folder, if the
+    /// folder has its own, otherwise the server's one
+    fn config_for(&self, file_name: &str) -> &Config {
+        self.folder_of(file_name)
+            .and_then(|folder| folder.config.as_ref())
+            .unwrap_or(&self.config)
+    }
+
+    /// Whether `file_name` is out of the workspace or skipped by the searcher,
+    /// because of itself or of one of its parents inside its folder
+    fn is_skipped(&self, file_name: &str) -> bool {
+        let Some(folder) = self.folder_of(file_name) else {
+            return true;
+        };
+        let Ok(relative) = Path::new(file_name).strip_prefix(&folder.root) else {
+            return true;
+        };
+
+        relative
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .any(|path| self.grep.should_skip(path))
+    }
+
+    /// Searches a file or a directory on disk, unless it is skipped
+    fn search_on_disk(&self, file_name: &str) -> State {
+        let mut state = State::default();
+        if self.is_skipped(file_name) {
+            return state;
+        }
+
+        let path = Path::new(file_name);
+        if path.is_dir() {
+            state = self.search_in_folder(file_name);
+        } else if let Some(mut file_state) = self.grep.search_in_path(path) {
+            let config = self.config_for(file_name);
+            file_state.retain(|m| config.highlights.contains_key(m.keyword()));
+            if !file_state.is_empty() {
+                state.insert(file_name.to_owned(), file_state);
+            }
+        }
+
+        state
+    }
+
+    /// Entries at the top of the workspace folders, which the initial scan
+    /// goes through one at a time
+    fn top_level_entries(&self) -> Vec<String> {
+        self.folders
+            .iter()
+            .filter_map(|folder| fs::read_dir(&folder.root).ok())
+            .flatten()
+            .filter_map(|entry| Some(entry.ok()?.path().to_str()?.to_owned()))
+            .filter(|file_name| !self.is_skipped(file_name))
+            .collect()
+    }
+
+    /// Scans a folder, keeping only the keywords configured for each file
+    fn search_in_folder(&self, root: &str) -> State {
+        let mut state = self.grep.recurssive_search(root);
+        state.retain_matches(|file_name, m| {
+            self.config_for(file_name)
+                .highlights
+                .contains_key(m.keyword())
+        });
+
+        state
+    }
+}
+
+/// A workspace folder and, when its settings define one, its configuration
+#[derive(Clone)]
+struct Folder {
+    root: String,
+    config: Option<Config>,
+    /// Ticket links compiled from `config`
+    tickets: Option<TicketLinks>,
+}
+
+impl Folder {
+    fn new(root: String) -> Self {
+        Self {
+            root,
+            config: None,
+            tickets: None,
+        }
+    }
+
+    /// Replaces the configuration, unless its ticket patterns are invalid
+    fn set_config(&mut self, config: Option<Config>) -> TodoResult<()> {
+        self.tickets = config
+            .as_ref()
+            .map(|config| TicketLinks::try_new(&config.ticket_patterns))
+            .transpose()?;
+        self.config = config;
+
+        Ok(())
+    }
+}
+
+/// Key of a document in the state: its path on disk
+fn file_key(uri: &Uri) -> String {
+    uri.to_file_path().map_or_else(
+        || uri.path().as_str().to_owned(),
+        |path| path.to_string_lossy().into_owned(),
+    )
+}
+
+/// Whether the relative `path` stays inside the folder it is joined to: it is
+/// neither absolute nor goes up with `..`
+fn stays_inside(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// The text of `line` before the byte `column`
+fn line_prefix(line: &str, column: Column) -> &str {
+    line.get(..column.column()).unwrap_or(line)
+}
+
+/// Position in a document of an LSP position, whose character is counted in
+/// `encoding`, the column being a byte offset in the line
+fn document_position(
+    encoding: PositionEncoding,
+    document: &Document,
+    position: ls_types::Position,
+) -> Position {
+    let row = Row::from(position.line as usize);
+    let line = document.line(row).unwrap_or_default();
+    let content = line.trim_end_matches(['\n', '\r']);
+    let column = encoding
+        .decode(
+            (position.character as usize).into(),
+            &WideChars::new(content),
+        )
+        .column()
+        .min(content.len());
+
+    Position::new(row, column.into())
+}
+
 pub struct Backend<S, C>
 where
     S: Searcher,
@@ -51,41 +548,713 @@ where
     protected: Arc<RwLock<Protected<State, Search<S>, Highlight<C>>>>,
 }
 
+impl<S, C> Clone for Backend<S, C>
+where
+    S: Searcher,
+    C: Colorer,
+{
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            protected: Arc::clone(&self.protected),
+        }
+    }
+}
+
 impl<S, C> Backend<S, C>
 where
-    S: RegexSearcher,
+    S: RegexSearcher + Clone + Send + Sync + 'static,
     C: Colorer,
 {
-    async fn update_colors(&self, config: &Config) {
+    /// Starts a scan of the whole workspace, superseding the ones in
+    /// progress. Returns the generation of the scan and a copy of the searcher
+    /// and the configurations, for the scan not to hold the lock.
+    async fn start_scan(&self) -> (usize, Scanner<Search<S>>) {
+        let mut protected = self.protected.write().await;
+        protected.scan_generation += 1;
+
+        (protected.scan_generation, protected.scanner.clone())
+    }
+
+    /// Registers again the semantic tokens when the keywords with a
+    /// foreground color changed. Clients which cannot register them
+    /// dynamically keep the legend advertised on initialization.
+    async fn update_token_types(&self) {
+        let token_types = {
+            let mut protected = self.protected.write().await;
+            let token_types = protected.foreground_keywords();
+            if !protected.registers_semantic_tokens() || token_types == protected.token_types {
+                return;
+            }
+
+            protected.token_types = token_types.clone();
+            token_types
+        };
+
+        let unregistration = Unregistration {
+            id: SEMANTIC_TOKENS_REGISTRATION.to_owned(),
+            method: SEMANTIC_TOKENS_METHOD.to_owned(),
+        };
+        let _ = self
+            .client
+            .unregister_capability(vec![unregistration])
+            .await;
+        self.register_semantic_tokens(&token_types).await;
+        let _ = self.client.semantic_tokens_refresh().await;
+    }
+
+    /// Registers the semantic tokens with a legend made of `token_types`
+    async fn register_semantic_tokens(&self, token_types: &[String]) {
+        let options = SemanticTokensRegistrationOptions {
+            text_document_registration_options: TextDocumentRegistrationOptions {
+                document_selector: None,
+            },
+            semantic_tokens_options: semantic_tokens_options(token_types),
+            static_registration_options: StaticRegistrationOptions::default(),
+        };
+        let registration = Registration {
+            id: SEMANTIC_TOKENS_REGISTRATION.to_owned(),
+            method: SEMANTIC_TOKENS_METHOD.to_owned(),
+            register_options: Some(json!(options)),
+        };
+
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Cannot register the semantic tokens: {e:?}"),
+                )
+                .await;
+        }
+    }
+
+    async fn semantic_tokens_in(&self, uri: &Uri, rows: RangeInclusive<Row>) -> SemanticTokens {
+        let protected = self.protected.read().await;
+        let file_name = file_key(uri);
+        // The legend may be the one of the initialization, listing keywords
+        // whose foreground color was removed since
+        let token_types = protected
+            .token_types
+            .iter()
+            .map(|keyword| {
+                protected
+                    .color(&file_name, keyword, ColorType::Foreground)
+                    .map(|_| keyword.as_str())
+            })
+            .collect::<Vec<_>>();
+        let data = protected
+            .state
+            .get(&file_name)
+            .filter(|_| protected.highlighting)
+            .map(|file_state| semantic_tokens(&token_types, protected.encoder(file_state), rows))
+            .unwrap_or_default();
+
+        SemanticTokens {
+            result_id: None,
+            data,
+        }
+    }
+
+    async fn update_regex(&self) -> TodoResult<()> {
+        // clone the keys while holding a read lock, then acquire a write lock
+        // only for the `grep.update_regex` call to avoid borrow conflicts
+        let read = self.protected.read().await;
+        let owned_keys: Vec<String> = read.keywords();
+        drop(read);
+
+        let keys_ref: Vec<&str> = owned_keys.iter().map(|s| s.as_str()).collect();
         self.protected
             .write()
             .await
-            .highlighter
-            .update_palette(config.highlights.clone());
+            .scanner
+            .grep
+            .update_regex(&keys_ref)?;
+
+        Ok(())
+    }
+
+    /// Pushes the diagnostics of a document, unless the client pulls them
+    async fn publish_diagnostics(&self, uri: Uri, version: Option<i32>) {
+        let diagnostics = {
+            let protected = self.protected.read().await;
+            if protected.pulls_diagnostics() {
+                return;
+            }
+
+            protected
+                .state
+                .get(&file_key(&uri))
+                .map(|file_state| {
+                    diagnostics(
+                        protected.scanner.config_for(&file_key(&uri)),
+                        protected.encoder(file_state),
+                    )
+                })
+                .unwrap_or_default()
+        };
+
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+
+    /// Pushes the diagnostics of every file, or asks the client to pull them
+    /// again when it pulls diagnostics
+    async fn publish_workspace_diagnostics(&self) {
+        let all_diagnostics = {
+            let protected = self.protected.read().await;
+            if protected.pulls_diagnostics() {
+                drop(protected);
+                let _ = self.client.workspace_diagnostic_refresh().await;
+                return;
+            }
+
+            protected
+                .state
+                .iter()
+                .filter_map(|(file_name, file_state)| {
+                    let uri = Uri::from_file_path(file_name)?;
+                    let config = protected.scanner.config_for(file_name);
+
+                    Some((uri, diagnostics(config, protected.encoder(file_state))))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (uri, diagnostics) in all_diagnostics {
+            self.client
+                .publish_diagnostics(uri, diagnostics, None)
+                .await;
+        }
+    }
+
+    /// Clears the pushed diagnostics of a file left without matches
+    async fn clear_diagnostics(&self, uri: Uri) {
+        if !self.protected.read().await.pulls_diagnostics() {
+            self.client.publish_diagnostics(uri, vec![], None).await;
+        }
+    }
+
+    async fn list_file(&self, arguments: Vec<Value>) -> Result<Option<Value>> {
+        let uri = arguments
+            .into_iter()
+            .next()
+            .and_then(|argument| serde_json::from_value::<Uri>(argument).ok())
+            .ok_or_else(|| Error::invalid_params("Expected the URI of a file"))?;
+
+        let (lines, matches) = {
+            let protected = self.protected.read().await;
+            let file_state = protected.state.get(&file_key(&uri));
+
+            file_state
+                .into_iter()
+                .flat_map(|file_state| {
+                    let encoder = protected.encoder(file_state);
+                    file_state.matches().map(move |(row, m)| (encoder, row, m))
+                })
+                .map(|(encoder, row, m)| {
+                    let line = format!("{}: {}", row.row() + 1, m.summary());
+                    let value = json!({
+                        "line": row.row(),
+                        "character": encoder.column(row, *m.column()).column(),
+                        "keyword": m.keyword(),
+                        "text": m.text(),
+                    });
+
+                    (line, value)
+                })
+                .unzip::<_, _, Vec<_>, Vec<_>>()
+        };
+
+        if !lines.is_empty() {
+            self.client
+                .show_message(MessageType::INFO, lines.join("\n"))
+                .await;
+        }
+
+        Ok(Some(Value::Array(matches)))
+    }
+
+    /// Every match of the workspace, sorted by file and row. Files are named
+    /// relatively to `root` when they are below it, by their URI otherwise.
+    async fn report_entries(&self, root: Option<&Path>) -> Vec<ReportEntry> {
+        let protected = self.protected.read().await;
+        let mut files = protected.state.iter().collect::<Vec<_>>();
+        files.sort_by_key(|(file_name, _)| *file_name);
+
+        files
+            .into_iter()
+            .flat_map(|(file_name, file_state)| {
+                let file = root
+                    .and_then(|root| Path::new(file_name).strip_prefix(root).ok())
+                    .map(|relative| relative.to_string_lossy().into_owned())
+                    .or_else(|| Uri::from_file_path(file_name).map(|uri| uri.to_string()))
+                    .unwrap_or_else(|| file_name.to_owned());
+
+                let encoder = protected.encoder(file_state);
+
+                file_state
+                    .matches()
+                    .map(move |(row, m)| ReportEntry::new(file.clone(), encoder, row, m))
+            })
+            .collect()
+    }
+
+    /// Answers the `todo/query` request with the matches of the workspace
+    /// meeting its filters, files being named by their URI
+    pub async fn query(&self, params: QueryParams) -> Result<QueryResponse> {
+        let globs = params
+            .path_globs()
+            .map_err(|e| Error::invalid_params(format!("{e:?}")))?;
+        let filter = params
+            .filter()
+            .map_err(|e| Error::invalid_params(format!("{e:?}")))?;
+        let protected = self.protected.read().await;
+
+        let mut items = protected
+            .state
+            .iter()
+            .filter(|(file_name, _)| {
+                globs.as_ref().is_none_or(|globs| {
+                    let path = Path::new(file_name);
+                    let relative = protected
+                        .scanner
+                        .folder_of(file_name)
+                        .and_then(|folder| path.strip_prefix(&folder.root).ok())
+                        .unwrap_or(path);
+
+                    globs.is_match(relative)
+                })
+            })
+            .filter_map(|(file_name, file_state)| {
+                Some((Uri::from_file_path(file_name)?, file_state))
+            })
+            .flat_map(|(uri, file_state)| {
+                let encoder = protected.encoder(file_state);
+                let filter = &filter;
+
+                file_state
+                    .matches()
+                    .filter(move |(_, m)| filter.accepts(m))
+                    .map(move |(row, m)| ReportEntry::new(uri.to_string(), encoder, row, m))
+            })
+            .collect::<Vec<_>>();
+        items.sort_by(|a, b| params.sort.compare(a, b));
+
+        let total = items.len();
+        items.truncate(params.limit.unwrap_or(total));
+
+        Ok(QueryResponse { total, items })
+    }
+
+    async fn toggle_highlighting(&self) -> Result<Option<Value>> {
+        let highlighting = {
+            let mut protected = self.protected.write().await;
+            protected.highlighting = !protected.highlighting;
+            protected.highlighting
+        };
+
+        // Document colors have no refresh request, editors ask for them again
+        // when the document changes
+        let _ = self.client.semantic_tokens_refresh().await;
+
+        Ok(Some(json!(highlighting)))
+    }
+
+    /// Writes a report of the matches into the first workspace folder. The
+    /// optional argument is an object with a `format`, either `markdown` (the
+    /// default) or `json`, and a `path` relative to the folder, which may not
+    /// leave it.
+    async fn export_report(&self, arguments: Vec<Value>) -> Result<Option<Value>> {
+        let options = arguments.into_iter().next().unwrap_or_default();
+        let json_format = match options.get("format").and_then(Value::as_str) {
+            None | Some("markdown") => false,
+            Some("json") => true,
+            Some(format) => {
+                return Err(Error::invalid_params(format!(
+                    "Unknown report format {format}"
+                )));
+            }
+        };
+        let default_path = match json_format {
+            true => "TODO_REPORT.json",
+            false => "TODO_REPORT.md",
+        };
+        let path = options
+            .get("path")
+            .and_then(Value::as_str)
+            .unwrap_or(default_path);
+        if !stays_inside(Path::new(path)) {
+            return Err(Error::invalid_params(format!(
+                "The report path {path} leaves the workspace folder"
+            )));
+        }
+
+        let root = self
+            .protected
+            .read()
+            .await
+            .scanner
+            .folders
+            .first()
+            .map(|folder| folder.root.clone())
+            .ok_or_else(|| Error::invalid_params("No workspace folder to write into"))?;
+        let entries = self.report_entries(Some(Path::new(&root))).await;
+        let report = match json_format {
+            true => serde_json::to_string_pretty(&entries).unwrap_or_default(),
+            false => MarkdownReportPresenter::convert(entries),
+        };
+
+        let destination = Path::new(&root).join(path);
+        fs::write(&destination, report).map_err(|e| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Cannot write {}: {e}", destination.display()).into(),
+            data: None,
+        })?;
+
+        Ok(Uri::from_file_path(&destination).map(|uri| json!(uri)))
+    }
+
+    async fn apply_config(&self, config: Config) -> TodoResult<()> {
+        self.client
+            .log_message(MessageType::LOG, format!("{config:?}"))
+            .await;
+        let tickets = TicketLinks::try_new(&config.ticket_patterns)?;
+
+        // The regex is built from the stored configuration, which is rolled
+        // back if the regex is invalid, so that nothing is half-applied
+        let mut protected = self.protected.write().await;
+        let previous = std::mem::replace(&mut protected.scanner.config, config);
+        let keywords = protected.keywords();
+        if let Err(e) = protected.scanner.grep.update_regex(&keywords) {
+            protected.scanner.config = previous;
+            return Err(e);
+        }
+
+        let highlights = protected.scanner.config.highlights.clone();
+        protected.highlighter.replace_palette(highlights);
+        protected.tickets = tickets;
+
+        Ok(())
+    }
+
+    /// Rebuilds the state from the workspace folders, the open documents
+    /// taking precedence over their content on disk. Returns the documents
+    /// which no longer have any match, none when a later scan superseded this
+    /// one.
+    async fn rescan(&self) -> Vec<Uri> {
+        let (generation, scanner) = self.start_scan().await;
+        let mut state = tokio::task::spawn_blocking(move || {
+            let mut state = State::default();
+            for folder in &scanner.folders {
+                state.extend(scanner.search_in_folder(&folder.root));
+            }
+
+            state
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut protected = self.protected.write().await;
+        if protected.scan_generation != generation {
+            return vec![];
+        }
+        for (file_name, document) in &protected.documents {
+            let file_state = protected.search_in_text(file_name, &document.text());
+            state.overlay(file_name.clone(), file_state);
+        }
+        let cleared = protected
+            .state
+            .iter()
+            .filter(|(file_name, _)| state.get(file_name).is_none())
+            .filter_map(|(file_name, _)| Uri::from_file_path(file_name))
+            .collect();
+        protected.state = state;
+
+        cleared
+    }
+
+    /// Reads the configuration from the notification's settings, falling back
+    /// to pulling the server's section from the client. Settings which no
+    /// longer define one, e.g. deleted ones, bring back the initialization
+    /// options.
+    async fn changed_config(&self, settings: Value) -> Config {
+        let pushed = settings
+            .get(CONFIG_SECTION)
+            .cloned()
+            .and_then(Config::try_parse_json)
+            .or_else(|| Config::try_parse_json(settings));
+        if let Some(config) = pushed {
+            return config;
+        }
+
+        let pulled = self
+            .client
+            .configuration(vec![ConfigurationItem {
+                scope_uri: None,
+                section: Some(CONFIG_SECTION.to_owned()),
+            }])
+            .await
+            .ok()
+            .and_then(|values| values.into_iter().next())
+            .and_then(Config::try_parse_json);
+
+        match pulled {
+            Some(config) => config,
+            None => self.protected.read().await.init_config.clone(),
+        }
+    }
+
+    /// Pulls the configuration of every workspace folder, folders without
+    /// their own configuration falling back to the server's one
+    async fn pull_folder_configs(&self) {
+        let items = self
+            .protected
+            .read()
+            .await
+            .scanner
+            .folders
+            .iter()
+            .map(|folder| ConfigurationItem {
+                scope_uri: Uri::from_file_path(&folder.root),
+                section: Some(CONFIG_SECTION.to_owned()),
+            })
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return;
+        }
+
+        let Ok(values) = self.client.configuration(items).await else {
+            return;
+        };
+
+        let mut protected = self.protected.write().await;
+        let mut invalid = vec![];
+        for (folder, value) in protected.scanner.folders.iter_mut().zip(values) {
+            if let Err(e) = folder.set_config(Config::try_parse_json(value)) {
+                invalid.push(format!("Invalid configuration of {}: {e:?}", folder.root));
+            }
+        }
+        drop(protected);
+
+        for message in invalid {
+            self.client.show_message(MessageType::ERROR, message).await;
+        }
+    }
+
+    /// Purges the files of the removed folders and scans the added ones
+    async fn update_folders(&self, removed: Vec<String>, added: Vec<String>) {
+        let purged = {
+            let mut protected = self.protected.write().await;
+            protected
+                .scanner
+                .folders
+                .retain(|folder| !removed.contains(&folder.root));
+            protected
+                .scanner
+                .folders
+                .extend(added.into_iter().map(Folder::new));
+
+            let protected = &mut *protected;
+            let (folders, documents) = (&protected.scanner.folders, &protected.documents);
+            protected.state.retain_files(|file_name| {
+                documents.contains_key(file_name)
+                    || folders
+                        .iter()
+                        .any(|folder| Path::new(file_name).starts_with(&folder.root))
+            })
+        };
+
+        for uri in purged.iter().filter_map(Uri::from_file_path) {
+            self.clear_diagnostics(uri).await;
+        }
+
+        self.pull_folder_configs().await;
+        self.update_token_types().await;
+        if let Err(e) = self.update_regex().await {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid configuration: {e:?}"))
+                .await;
+        }
+        self.rescan_and_publish().await;
+    }
+
+    async fn rescan_and_publish(&self) {
+        for uri in self.rescan().await {
+            self.clear_diagnostics(uri).await;
+        }
+        self.publish_workspace_diagnostics().await;
+    }
+
+    /// Scans the workspace folders in the background, an entry of their top
+    /// level at a time, merging each entry into the state once scanned so
+    /// that requests are served meanwhile
+    async fn scan_workspace(&self) {
+        let with_progress = self
+            .protected
+            .read()
+            .await
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        let (generation, scanner) = self.start_scan().await;
+        let scanner = Arc::new(scanner);
+        let entries = scanner.top_level_entries();
+
+        let token = ProgressToken::String(SCAN_PROGRESS_TOKEN.to_owned());
+        let progress = match with_progress
+            && self
+                .client
+                .create_work_done_progress(token.clone())
+                .await
+                .is_ok()
+        {
+            true => Some(
+                self.client
+                    .progress(token, "Scanning workspace")
+                    .with_percentage(0)
+                    .begin()
+                    .await,
+            ),
+            false => None,
+        };
+
+        let mut scanned_files = 0;
+        for (i, entry) in entries.iter().enumerate() {
+            let (scanner, entry) = (Arc::clone(&scanner), entry.clone());
+            let scanned = tokio::task::spawn_blocking(move || scanner.search_on_disk(&entry))
+                .await
+                .unwrap_or_default();
+            // A rescan following a change of the configuration or of the
+            // folders supersedes the entries left, scanned with the old ones
+            let mut protected = self.protected.write().await;
+            if protected.scan_generation != generation {
+                break;
+            }
+            scanned_files += scanned.iter().count();
+            protected.state.extend(scanned);
+            drop(protected);
+
+            if let Some(progress) = &progress {
+                let message = format!(
+                    "{} of {} entries, {scanned_files} files with matches",
+                    i + 1,
+                    entries.len()
+                );
+                let percentage = (100 * (i + 1) / entries.len()) as u32;
+                progress.report_with_message(message, percentage).await;
+            }
+        }
+
+        if let Some(progress) = progress {
+            progress
+                .finish_with_message(format!("{scanned_files} files with matches"))
+                .await;
+        }
+        self.publish_workspace_diagnostics().await;
+        let _ = self.client.code_lens_refresh().await;
+    }
+
+    /// Reads again from disk the files or directories at `uris`, ignoring
+    /// the skipped ones, e.g. the build outputs
+    async fn reload_files(&self, uris: impl Iterator<Item = String>) {
+        let mut protected = self.protected.write().await;
+        let file_names = uris
+            .filter_map(|uri| uri.parse::<Uri>().ok())
+            .map(|uri| file_key(&uri))
+            .filter(|file_name| !protected.scanner.is_skipped(file_name))
+            .collect::<Vec<_>>();
+        let purged = file_names
+            .iter()
+            .flat_map(|file_name| protected.reload_from_disk(file_name))
+            .collect::<Vec<_>>();
+        let reloaded = file_names
+            .iter()
+            .flat_map(|file_name| protected.files_under(file_name))
+            .collect::<Vec<_>>();
+        drop(protected);
+
+        self.publish_reloaded(reloaded, purged).await;
     }
 
-    async fn update_regex(&self) -> TodoResult<()> {
-        // clone the keys while holding a read lock, then acquire a write lock
-        // only for the `grep.update_regex` call to avoid borrow conflicts
-        let read = self.protected.read().await;
-        let owned_keys: Vec<String> = read.highlighter.colors().keys().cloned().collect();
-        drop(read);
+    /// Publishes the diagnostics of the files read again and clears the ones
+    /// of the files which no longer have any match
+    async fn publish_reloaded(&self, reloaded: Vec<String>, purged: Vec<String>) {
+        if reloaded.is_empty() && purged.is_empty() {
+            return;
+        }
+        if self.protected.read().await.pulls_diagnostics() {
+            let _ = self.client.workspace_diagnostic_refresh().await;
+            return;
+        }
 
-        let keys_ref: Vec<&str> = owned_keys.iter().map(|s| s.as_str()).collect();
-        self.protected.write().await.grep.update_regex(&keys_ref)?;
+        for uri in purged.iter().filter_map(Uri::from_file_path) {
+            self.client.publish_diagnostics(uri, vec![], None).await;
+        }
+        for uri in reloaded.iter().filter_map(Uri::from_file_path) {
+            self.publish_diagnostics(uri, None).await;
+        }
+    }
 
-        Ok(())
+    /// Watches the files of the workspace, so that changes made outside of
+    /// the editor are picked up
+    async fn register_file_watchers(&self) {
+        let dynamic = self
+            .protected
+            .read()
+            .await
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files)
+            .and_then(|watched_files| watched_files.dynamic_registration)
+            .unwrap_or(false);
+        if !dynamic {
+            return;
+        }
+
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/*".to_owned()),
+                kind: None,
+            }],
+        };
+        let registration = Registration {
+            id: WATCHED_FILES_REGISTRATION.to_owned(),
+            method: "workspace/didChangeWatchedFiles".to_owned(),
+            register_options: Some(json!(options)),
+        };
+
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(MessageType::WARNING, format!("Cannot watch files: {e:?}"))
+                .await;
+        }
     }
 
     async fn init(&self, params: InitializeParams) -> Result<()> {
+        {
+            let mut protected = self.protected.write().await;
+            protected.capabilities = params.capabilities.clone();
+            protected.encoding = PositionEncoding::negotiate(
+                params
+                    .capabilities
+                    .general
+                    .as_ref()
+                    .and_then(|general| general.position_encodings.as_deref()),
+            );
+        }
+
         let config = Config::parse_json(params.initialization_options.unwrap_or_default());
-        self.client
-            .log_message(MessageType::LOG, format!("{config:?}"))
-            .await;
-        self.update_colors(&config).await;
-        self.update_regex()
+        self.protected.write().await.init_config = config.clone();
+        self.apply_config(config)
             .await
             .map_err(|e| Error::invalid_params(format!("{e:?}")))?;
+        let mut protected = self.protected.write().await;
+        protected.token_types = protected.foreground_keywords();
+        drop(protected);
 
         match params.workspace_folders {
             Some(ref folders) => {
@@ -93,15 +1262,10 @@ where
                     return Err(Error::invalid_params("Workspace folders are empty"));
                 }
 
-                let uri = &folders[0].uri;
-                let intial_state = self
-                    .protected
-                    .read()
-                    .await
-                    .grep
-                    .recurssive_search(uri.path().as_str());
-
-                self.protected.write().await.state.extend(intial_state);
+                self.protected.write().await.scanner.folders = folders
+                    .iter()
+                    .map(|folder| Folder::new(file_key(&folder.uri)))
+                    .collect();
 
                 Ok(())
             }
@@ -112,7 +1276,7 @@ where
 
 impl<S, C> LanguageServer for Backend<S, C>
 where
-    S: RegexSearcher + Send + Sync + 'static,
+    S: RegexSearcher + Clone + Send + Sync + 'static,
     C: Colorer + Send + Sync + 'static,
 {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
@@ -120,27 +1284,93 @@ where
 
         self.init(params).await?;
 
+        let file_operations = FileOperationRegistrationOptions {
+            filters: vec![FileOperationFilter {
+                scheme: Some("file".to_owned()),
+                pattern: FileOperationPattern {
+                    glob: "**/*".to_owned(),
+                    matches: None,
+                    options: None,
+                },
+            }],
+        };
+
+        let protected = self.protected.read().await;
+        let encoding = protected.encoding;
+        // Clients registering the semantic tokens dynamically get them once
+        // initialized, along with the keywords of the folders' configs
+        let semantic_tokens = (!protected.registers_semantic_tokens())
+            .then(|| semantic_tokens_options(&protected.token_types).into());
+        drop(protected);
+
         Ok(InitializeResult {
             server_info: Some(ServerInfo {
                 name: LSP_NAME.into(),
                 version: Some(env!("CARGO_PKG_VERSION").into()),
             }),
             capabilities: ServerCapabilities {
+                position_encoding: Some(encoding.kind()),
                 workspace: Some(WorkspaceServerCapabilities {
                     workspace_folders: Some(WorkspaceFoldersServerCapabilities {
                         supported: Some(true),
                         change_notifications: Some(OneOf::Left(true)),
                     }),
-                    file_operations: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        did_create: Some(file_operations.clone()),
+                        did_rename: Some(file_operations.clone()),
+                        did_delete: Some(file_operations),
+                        ..Default::default()
+                    }),
                 }),
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::FULL),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
                         ..Default::default()
                     },
                 )),
                 color_provider: Some(ColorProviderCapability::Simple(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some(DIAGNOSTIC_SOURCE.to_owned()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: true,
+                        work_done_progress_options: Default::default(),
+                    },
+                )),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                completion_provider: Some(CompletionOptions::default()),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                semantic_tokens_provider: semantic_tokens,
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: [
+                        LIST_FILE_COMMAND,
+                        RESCAN_WORKSPACE_COMMAND,
+                        LIST_ALL_COMMAND,
+                        TOGGLE_HIGHLIGHTING_COMMAND,
+                        EXPORT_REPORT_COMMAND,
+                    ]
+                    .map(str::to_owned)
+                    .to_vec(),
+                    ..Default::default()
+                }),
                 ..ServerCapabilities::default()
             },
         })
@@ -150,25 +1380,130 @@ where
         self.client
             .log_message(MessageType::INFO, "server initialized!")
             .await;
+
+        self.pull_folder_configs().await;
+        let (dynamic, token_types) = {
+            let mut protected = self.protected.write().await;
+            protected.token_types = protected.foreground_keywords();
+            (
+                protected.registers_semantic_tokens(),
+                protected.token_types.clone(),
+            )
+        };
+        if dynamic {
+            self.register_semantic_tokens(&token_types).await;
+        }
+        let has_folder_configs = self
+            .protected
+            .read()
+            .await
+            .scanner
+            .folders
+            .iter()
+            .any(|folder| folder.config.is_some());
+        if has_folder_configs && let Err(e) = self.update_regex().await {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid configuration: {e:?}"))
+                .await;
+        }
+
+        let backend = self.clone();
+        tokio::spawn(async move { backend.scan_workspace().await });
+        self.register_file_watchers().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let config = self.changed_config(params.settings).await;
+        if let Err(e) = self.apply_config(config).await {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid configuration: {e:?}"))
+                .await;
+            return;
+        }
+
+        self.pull_folder_configs().await;
+        self.update_token_types().await;
+        if let Err(e) = self.update_regex().await {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid configuration: {e:?}"))
+                .await;
+            return;
+        }
+        self.rescan_and_publish().await;
+
+        // There is no refresh request for document colors, editors pull them
+        // again along with the other features when the document changes
+        let _ = self.client.semantic_tokens_refresh().await;
+        let _ = self.client.code_lens_refresh().await;
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let WorkspaceFoldersChangeEvent { added, removed } = params.event;
+        let to_roots = |folders: Vec<WorkspaceFolder>| {
+            folders
+                .iter()
+                .map(|folder| file_key(&folder.uri))
+                .collect::<Vec<_>>()
+        };
+
+        self.update_folders(to_roots(removed), to_roots(added))
+            .await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        // Every kind of event is handled the same way, by reading the disk
+        // again, which also covers the directories the events may refer to
+        let uris = params
+            .changes
+            .into_iter()
+            .map(|event| event.uri.to_string());
+        self.reload_files(uris).await;
+    }
+
+    async fn did_create_files(&self, params: CreateFilesParams) {
+        let uris = params.files.into_iter().map(|file| file.uri);
+        self.reload_files(uris).await;
+    }
+
+    async fn did_rename_files(&self, params: RenameFilesParams) {
+        let mut protected = self.protected.write().await;
+        let (mut reloaded, mut purged) = (vec![], vec![]);
+        for file in &params.files {
+            let (Ok(from), Ok(to)) = (file.old_uri.parse::<Uri>(), file.new_uri.parse::<Uri>())
+            else {
+                continue;
+            };
+            let to = file_key(&to);
+
+            purged.extend(protected.rename_file(&file_key(&from), &to));
+            reloaded.extend(protected.files_under(&to));
+        }
+        drop(protected);
+
+        self.publish_reloaded(reloaded, purged).await;
+    }
+
+    async fn did_delete_files(&self, params: DeleteFilesParams) {
+        let uris = params.files.into_iter().map(|file| file.uri);
+        self.reload_files(uris).await;
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let DidOpenTextDocumentParams { text_document } = params;
-        let TextDocumentItem { uri, text, .. } = text_document;
+        let TextDocumentItem {
+            uri, text, version, ..
+        } = text_document;
 
-        let possible_new_matches = self.protected.read().await.grep.search_in_text(&text);
+        self.protected
+            .write()
+            .await
+            .set_document(&file_key(&uri), &text);
 
-        if let Some(unknown_file_matches) = possible_new_matches {
-            self.protected
-                .write()
-                .await
-                .state
-                .replace(uri.path().as_str().to_owned(), unknown_file_matches);
-        }
+        self.publish_diagnostics(uri, Some(version)).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
@@ -176,60 +1511,78 @@ where
             text_document,
             content_changes,
         } = params;
-        let VersionedTextDocumentIdentifier { uri, .. } = text_document;
+        let VersionedTextDocumentIdentifier { uri, version } = text_document;
 
-        if let Some(last_change) = content_changes.last() {
-            let file_path = uri.path().as_str();
-            let new_matches = self
-                .protected
-                .read()
-                .await
-                .grep
-                .search_in_text(&last_change.text);
-
-            match new_matches {
-                Some(new_matches) => self
-                    .protected
-                    .write()
-                    .await
-                    .state
-                    .replace(file_path.to_owned(), new_matches),
-                None => self.protected.write().await.state.remove(file_path),
+        if content_changes.is_empty() {
+            return;
+        }
+
+        let file_name = file_key(&uri);
+        let mut protected = self.protected.write().await;
+        for change in content_changes {
+            match change.range {
+                Some(range) => protected.edit_document(&file_name, range, &change.text),
+                None => protected.set_document(&file_name, &change.text),
             }
         }
+        drop(protected);
+
+        self.publish_diagnostics(uri, Some(version)).await;
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        self.protected
+            .write()
+            .await
+            .state
+            .promote(&file_key(&params.text_document.uri));
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.protected.write().await.close_document(&file_key(&uri));
+
+        self.publish_diagnostics(uri, None).await;
     }
 
     async fn document_color(&self, params: DocumentColorParams) -> Result<Vec<ColorInformation>> {
-        let protected = self.protected.read().await;
+        let protected = &*self.protected.read().await;
+        let file_name = file_key(&params.text_document.uri);
+        let file_name = file_name.as_str();
 
         let highlights = protected
             .state
-            .get(params.text_document.uri.path().as_str())
+            .get(file_name)
+            .filter(|_| protected.highlighting)
             .into_iter()
             .flat_map(|file_state| {
+                let encoder = protected.encoder(file_state);
+
                 file_state
                     .rows()
                     .iter()
-                    .map(|(row, (row_meta, row_matches))| {
+                    .map(move |(row, (row_meta, row_matches))| {
                         let intervals =
                             protected.highlighter.color_intervals(row_matches, row_meta);
 
-                        intervals.into_iter().enumerate().map(
-                            |(i, (color_patch_start, color_patch_end))| {
-                                let start_pos = Position::new(*row, color_patch_start);
-                                let end_pos = Position::new(*row, color_patch_end);
-                                let color = protected
-                                    .highlighter
-                                    .highlight(row_matches[i].keyword(), ColorType::Background)
-                                    .unwrap();
-
-                                ColorInformation {
-                                    range: Range {
-                                        start: PositionPresenter::convert(start_pos),
-                                        end: PositionPresenter::convert(end_pos),
-                                    },
+                        // A keyword dropped from the configuration keeps its
+                        // matches until the rescan, they go uncolored meanwhile
+                        intervals.into_iter().enumerate().filter_map(
+                            move |(i, (color_patch_start, color_patch_end))| {
+                                let color = protected.color(
+                                    file_name,
+                                    row_matches[i].keyword(),
+                                    ColorType::Background,
+                                )?;
+
+                                Some(ColorInformation {
+                                    range: encoder.row_range(
+                                        *row,
+                                        color_patch_start,
+                                        color_patch_end,
+                                    ),
                                     color: ColorPresenter::convert(color),
-                                }
+                                })
                             },
                         )
                     })
@@ -239,6 +1592,461 @@ where
 
         Ok(highlights)
     }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position_params = params.text_document_position_params;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&position_params.text_document.uri);
+        let (row, column) = protected.row_column(&file_name, position_params.position);
+
+        Ok(protected
+            .state
+            .get(&file_name)
+            .and_then(|file_state| {
+                let m = file_state.match_at(row, column)?;
+                Some((protected.encoder(file_state).keyword_range(row, m), m))
+            })
+            .map(|(range, m)| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: hover_contents(
+                        protected.scanner.config_for(&file_name),
+                        &protected.state,
+                        m,
+                    ),
+                }),
+                range: Some(range),
+            }))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&text_document.uri);
+        let (row, column) = protected.row_column(&file_name, position);
+
+        let Some(reference) = protected
+            .state
+            .get(&file_name)
+            .and_then(|file_state| file_state.annotated_match_at(row, column))
+            .and_then(|m| m.reference_at(column))
+        else {
+            return Ok(None);
+        };
+
+        let include_declaration = params.context.include_declaration;
+        let declaration = (file_name.as_str(), row);
+        let mut files = protected.state.iter().collect::<Vec<_>>();
+        files.sort_by_key(|(file_name, _)| *file_name);
+        let locations = files
+            .into_iter()
+            .filter_map(|(other, file_state)| {
+                Some((other, Uri::from_file_path(other)?, file_state))
+            })
+            .flat_map(|(other, uri, file_state)| {
+                let encoder = protected.encoder(file_state);
+
+                file_state
+                    .matches()
+                    .filter(|(_, m)| m.annotation().refers_to(reference))
+                    .filter(move |(other_row, _)| {
+                        include_declaration || (other, *other_row) != declaration
+                    })
+                    .map(move |(other_row, m)| Location {
+                        uri: uri.clone(),
+                        range: encoder.annotated_range(other_row, m),
+                    })
+            })
+            .collect();
+
+        Ok(Some(locations))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let protected = self.protected.read().await;
+        let file_name = file_key(&params.text_document.uri);
+        let (row, column) = protected.row_column(&file_name, params.position);
+
+        Ok(protected
+            .state
+            .get(&file_name)
+            .and_then(|file_state| target_at(protected.encoder(file_state), row, column))
+            .map(|(target, range)| {
+                let placeholder = match target {
+                    Target::Keyword(name) | Target::Reference(name) => name,
+                };
+
+                PrepareRenameResponse::RangeWithPlaceholder {
+                    range,
+                    placeholder: placeholder.to_owned(),
+                }
+            }))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&text_document.uri);
+        let (row, column) = protected.row_column(&file_name, position);
+
+        let (target, _) = protected
+            .state
+            .get(&file_name)
+            .and_then(|file_state| target_at(protected.encoder(file_state), row, column))
+            .ok_or_else(|| Error::invalid_params("No keyword or tag to rename here"))?;
+
+        let new_name = params.new_name.trim();
+        let valid = match target {
+            Target::Keyword(_) => protected
+                .scanner
+                .config_for(&file_name)
+                .highlights
+                .contains_key(new_name),
+            Target::Reference(_) => !new_name.is_empty() && !new_name.contains([',', '(', ')']),
+        };
+        if !valid {
+            return Err(Error::invalid_params(match target {
+                Target::Keyword(_) => format!("{new_name} is not a configured keyword"),
+                Target::Reference(_) => format!("{new_name} is not a valid tag"),
+            }));
+        }
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(rename_edits(
+                protected.encoding,
+                &protected.state,
+                &target,
+                new_name,
+            )),
+            ..Default::default()
+        }))
+    }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position_params;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&text_document.uri);
+        let (row, column) = protected.row_column(&file_name, position);
+
+        Ok(protected.state.get(&file_name).and_then(|file_state| {
+            let encoder = protected.encoder(file_state);
+            let (target, _) = target_at(encoder, row, column)?;
+            let ranges = match target {
+                Target::Keyword(_) => target_ranges(encoder, &target),
+                Target::Reference(reference) => mention_ranges(encoder, reference),
+            };
+            let highlights = ranges
+                .into_iter()
+                .map(|range| DocumentHighlight {
+                    range,
+                    kind: Some(DocumentHighlightKind::TEXT),
+                })
+                .collect();
+
+            Some(highlights)
+        }))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let rows = Row::from(0)..=Row::from(usize::MAX);
+        let tokens = self
+            .semantic_tokens_in(&params.text_document.uri, rows)
+            .await;
+
+        Ok(Some(tokens.into()))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        let start = Row::from(params.range.start.line as usize);
+        let end = Row::from(params.range.end.line as usize);
+        let tokens = self
+            .semantic_tokens_in(&params.text_document.uri, start..=end)
+            .await;
+
+        Ok(Some(tokens.into()))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&text_document.uri);
+        let (row, column) = protected.row_column(&file_name, position);
+        let config = protected.scanner.config_for(&file_name);
+
+        let in_comment = protected
+            .documents
+            .get(&file_name)
+            .and_then(|document| document.line(row))
+            .is_some_and(|line| is_in_comment(line_prefix(line, column)));
+        if !in_comment {
+            return Ok(None);
+        }
+
+        let (user, date) = (user_name(), today());
+        let mut keywords = config.highlights.keys().collect::<Vec<_>>();
+        keywords.sort();
+
+        let items = keywords
+            .into_iter()
+            .map(|keyword| CompletionItem {
+                label: keyword.clone(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                detail: config.description(keyword).map(str::to_owned),
+                insert_text: Some(expand_template(&config.template(keyword), &user, &date)),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let protected = self.protected.read().await;
+        let file_name = file_key(&uri);
+        let config = protected.scanner.config_for(&file_name);
+
+        Ok(protected.state.get(&file_name).map(|file_state| {
+            let encoder = protected.encoder(file_state);
+
+            file_state
+                .rows()
+                .iter()
+                .flat_map(|(row, (row_meta, row_matches))| {
+                    row_matches
+                        .iter()
+                        .filter(|m| overlaps(encoder, &params.range, *row, m))
+                        .flat_map(|m| code_actions(&uri, config, encoder, *row, row_meta, m))
+                })
+                .collect()
+        }))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
+        let protected = self.protected.read().await;
+
+        Ok(protected.state.get(&file_key(&uri)).map(|file_state| {
+            let start = PositionPresenter::convert(Position::new(0.into(), 0.into()));
+
+            vec![CodeLens {
+                range: Range { start, end: start },
+                command: Some(Command {
+                    title: keyword_summary(file_state),
+                    command: LIST_FILE_COMMAND.to_owned(),
+                    arguments: Some(vec![json!(uri)]),
+                }),
+                data: None,
+            }]
+        }))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        match params.command.as_str() {
+            LIST_FILE_COMMAND => self.list_file(params.arguments).await,
+            RESCAN_WORKSPACE_COMMAND => {
+                self.rescan_and_publish().await;
+                let _ = self.client.code_lens_refresh().await;
+                Ok(None)
+            }
+            LIST_ALL_COMMAND => Ok(Some(json!(self.report_entries(None).await))),
+            TOGGLE_HIGHLIGHTING_COMMAND => self.toggle_highlighting().await,
+            EXPORT_REPORT_COMMAND => self.export_report(params.arguments).await,
+            command => Err(Error::invalid_params(format!("Unknown command {command}"))),
+        }
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let protected = self.protected.read().await;
+        let report = protected.diagnostic_report(
+            &file_key(&params.text_document.uri),
+            params.previous_result_id.as_deref(),
+        );
+
+        let report = match report {
+            Ok(full_document_diagnostic_report) => {
+                DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                    related_documents: None,
+                    full_document_diagnostic_report,
+                })
+            }
+            Err(unchanged_document_diagnostic_report) => {
+                DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                    related_documents: None,
+                    unchanged_document_diagnostic_report,
+                })
+            }
+        };
+
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        let protected = self.protected.read().await;
+        let previous_result_ids = params
+            .previous_result_ids
+            .into_iter()
+            .map(|previous| (file_key(&previous.uri), previous.value))
+            .collect::<HashMap<_, _>>();
+
+        // Files reported before but no longer having matches get an empty
+        // report, so that the client drops their diagnostics
+        let mut file_names = protected
+            .state
+            .iter()
+            .map(|(file_name, _)| file_name.to_owned())
+            .chain(previous_result_ids.keys().cloned())
+            .collect::<Vec<_>>();
+        file_names.sort();
+        file_names.dedup();
+
+        let items = file_names
+            .into_iter()
+            .filter_map(|file_name| {
+                let uri = Uri::from_file_path(&file_name)?;
+                let previous = previous_result_ids.get(&file_name).map(String::as_str);
+
+                Some(match protected.diagnostic_report(&file_name, previous) {
+                    Ok(full_document_diagnostic_report) => WorkspaceDocumentDiagnosticReport::Full(
+                        WorkspaceFullDocumentDiagnosticReport {
+                            uri,
+                            version: None,
+                            full_document_diagnostic_report,
+                        },
+                    ),
+                    Err(unchanged_document_diagnostic_report) => {
+                        WorkspaceDocumentDiagnosticReport::Unchanged(
+                            WorkspaceUnchangedDocumentDiagnosticReport {
+                                uri,
+                                version: None,
+                                unchanged_document_diagnostic_report,
+                            },
+                        )
+                    }
+                })
+            })
+            .collect();
+
+        Ok(WorkspaceDiagnosticReportResult::Report(
+            WorkspaceDiagnosticReport { items },
+        ))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let protected = self.protected.read().await;
+
+        Ok(protected
+            .state
+            .get(&file_key(&params.text_document.uri))
+            .map(folding_ranges))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let protected = self.protected.read().await;
+        let file_name = file_key(&params.text_document.uri);
+        let tickets = protected.tickets_for(&file_name);
+
+        Ok(protected
+            .state
+            .get(&file_name)
+            .map(|file_state| document_links(tickets, protected.encoder(file_state))))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let protected = self.protected.read().await;
+
+        Ok(protected
+            .state
+            .get(&file_key(&params.text_document.uri))
+            .map(|file_state| {
+                DocumentSymbolResponse::Nested(document_symbols(protected.encoder(file_state)))
+            }))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<WorkspaceSymbolResponse>> {
+        let protected = self.protected.read().await;
+
+        let mut ranked = protected
+            .state
+            .iter()
+            .flat_map(|(file_name, file_state)| {
+                let encoder = protected.encoder(file_state);
+
+                file_state
+                    .matches()
+                    .map(move |(row, m)| (file_name, encoder, row, m))
+            })
+            .filter_map(|(file_name, encoder, row, m)| {
+                let name = m.summary();
+                let score = fuzzy_score(&params.query, &name)?;
+
+                Some((score, file_name, row, encoder.keyword_range(row, m), name))
+            })
+            .collect::<Vec<_>>();
+
+        ranked.sort_by(|(score1, file1, row1, ..), (score2, file2, row2, ..)| {
+            score2
+                .cmp(score1)
+                .then_with(|| file1.cmp(file2))
+                .then_with(|| row1.cmp(row2))
+        });
+
+        let symbols = ranked
+            .into_iter()
+            .filter_map(|(_, file_name, _, range, name)| {
+                Some(WorkspaceSymbol {
+                    name,
+                    kind: SymbolKind::STRING,
+                    tags: None,
+                    container_name: Some(file_name.to_owned()),
+                    location: OneOf::Left(Location {
+                        uri: Uri::from_file_path(file_name)?,
+                        range,
+                    }),
+                    data: None,
+                })
+            })
+            .collect();
+
+        Ok(Some(WorkspaceSymbolResponse::Nested(symbols)))
+    }
 }
 
 pub fn new_server(client: Client, config: Config) -> Backend<RipGrepSearcher, ColorProvider> {
@@ -251,22 +2059,134 @@ pub fn new_server(client: Client, config: Config) -> Backend<RipGrepSearcher, Co
 
     let background_colors = config
         .highlights
-        .into_iter()
-        .map(|(key_word, user_colors)| {
-            let background = Color::new(
-                user_colors.background.r,
-                user_colors.background.g,
-                user_colors.background.b,
-                user_colors.background.a,
-            );
-            let colors = Colors::new(background);
-
-            (key_word, colors)
-        })
+        .iter()
+        .map(|(key_word, user_colors)| (key_word.clone(), Colors::from(user_colors)))
         .collect();
     let highlighter = Highlight::new(ColorProvider::new(background_colors));
 
-    let protected = Protected::new(state, searcher, highlighter);
+    let protected = Protected::new(state, searcher, highlighter, config);
 
     Backend { client, protected }
 }
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use tower_lsp_server::LspService;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn invalid_keywords_leave_the_configuration_untouched() {
+        let (service, _) = LspService::new(|client| new_server(client, Config::default()));
+        let backend = service.inner();
+        let config = |keyword: &str| {
+            Config::parse_json(json!({"highlights": {keyword: {"background": "#ff0000"}}}))
+        };
+
+        backend.apply_config(config("FIXME")).await.unwrap();
+        assert!(backend.apply_config(config("FIX(ME")).await.is_err());
+
+        let protected = backend.protected.read().await;
+        assert_eq!(protected.keywords(), vec!["FIXME"]);
+        assert!(protected.highlighter.colors().contains_key("FIXME"));
+        assert!(!protected.highlighter.colors().contains_key("FIX(ME"));
+    }
+
+    #[tokio::test]
+    async fn diagnostic_reports_are_unchanged_for_the_last_result_id() {
+        let (service, _) = LspService::new(|client| new_server(client, Config::default()));
+        let mut protected = service.inner().protected.write().await;
+        protected.state.insert(
+            "/a.rs".to_owned(),
+            RipGrepSearcher::todos_for_test("// TODO a\n"),
+        );
+
+        let full = protected.diagnostic_report("/a.rs", None).unwrap();
+        let result_id = full.result_id.unwrap();
+        assert_eq!(full.items.len(), 1);
+
+        let unchanged = protected.diagnostic_report("/a.rs", Some(&result_id));
+        assert_eq!(unchanged.unwrap_err().result_id, result_id);
+
+        let stale = protected.diagnostic_report("/a.rs", Some("stale")).unwrap();
+        assert_eq!(stale.result_id, Some(result_id));
+        assert_eq!(stale.items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn files_without_matches_get_an_empty_full_report() {
+        let (service, _) = LspService::new(|client| new_server(client, Config::default()));
+        let mut protected = service.inner().protected.write().await;
+        protected.state.insert(
+            "/a.rs".to_owned(),
+            RipGrepSearcher::todos_for_test("// TODO a\n"),
+        );
+        let result_id = protected
+            .diagnostic_report("/a.rs", None)
+            .unwrap()
+            .result_id;
+
+        protected.state.remove("/a.rs");
+        let report = protected
+            .diagnostic_report("/a.rs", result_id.as_deref())
+            .unwrap();
+
+        assert!(report.items.is_empty());
+        assert_ne!(report.result_id, result_id);
+    }
+
+    #[test]
+    fn folder_configs_with_invalid_ticket_patterns_are_rejected() {
+        let config = |regex: &str| {
+            Config::try_parse_json(json!({
+                "highlights": {},
+                "ticket_patterns": [{"regex": regex, "url": "https://example.com/$0"}],
+            }))
+        };
+        let mut folder = Folder::new("/workspace".to_owned());
+
+        folder.set_config(config("PROJ-[0-9]+")).unwrap();
+        assert!(folder.set_config(config("PROJ-(")).is_err());
+        assert_eq!(
+            folder.tickets.unwrap().links("see PROJ-12"),
+            vec![(4, 11, "https://example.com/PROJ-12".to_owned())]
+        );
+    }
+
+    #[tokio::test]
+    async fn token_types_follow_the_foreground_colors() {
+        let (service, _) = LspService::new(|client| new_server(client, Config::default()));
+        let backend = service.inner();
+        let config = |colors: Value| Config::parse_json(json!({"highlights": {"FIXME": colors}}));
+
+        backend
+            .apply_config(config(json!({"foreground": "#ff0000"})))
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.protected.read().await.foreground_keywords(),
+            vec!["FIXME"]
+        );
+
+        backend.apply_config(config(json!({}))).await.unwrap();
+        assert!(
+            backend
+                .protected
+                .read()
+                .await
+                .foreground_keywords()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn report_paths_cannot_leave_the_folder() {
+        assert!(stays_inside(Path::new("TODO_REPORT.md")));
+        assert!(stays_inside(Path::new("./docs/todo.json")));
+        assert!(!stays_inside(Path::new("../TODO_REPORT.md")));
+        assert!(!stays_inside(Path::new("docs/../../TODO_REPORT.md")));
+        assert!(!stays_inside(Path::new("/tmp/TODO_REPORT.md")));
+    }
+}
+// grcov-excl-stop
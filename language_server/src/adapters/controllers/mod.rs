@@ -1,3 +1,4 @@
 pub mod highlight;
 pub mod lsp;
+pub mod query;
 pub mod search;
@@ -0,0 +1,190 @@
+use std::cmp::Ordering;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adapters::presenters::ReportEntry,
+    entities::{Annotation, Error, TodoResult},
+    use_cases::MatchFilter,
+};
+
+/// How the results of a query are ordered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuerySort {
+    /// By file, then by line
+    #[default]
+    File,
+    /// By keyword, then by file and line
+    Keyword,
+    /// Oldest date first, the matches without a date coming last
+    Date,
+}
+
+impl QuerySort {
+    pub fn compare(self, a: &ReportEntry, b: &ReportEntry) -> Ordering {
+        let by_file = |a: &ReportEntry, b: &ReportEntry| {
+            a.file.cmp(&b.file).then_with(|| a.line.cmp(&b.line))
+        };
+
+        match self {
+            Self::File => by_file(a, b),
+            Self::Keyword => a.keyword.cmp(&b.keyword).then_with(|| by_file(a, b)),
+            Self::Date => match (&a.date, &b.date) {
+                (Some(date_a), Some(date_b)) => date_a.cmp(date_b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+            .then_with(|| by_file(a, b)),
+        }
+    }
+}
+
+/// Parameters of the `todo/query` request, every filter being optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QueryParams {
+    pub keywords: Vec<String>,
+    /// Globs the path of the file has to match, relatively to its workspace
+    /// folder, e.g. `src/**/*.rs`
+    pub paths: Vec<String>,
+    pub author: Option<String>,
+    pub ticket: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub text: Option<String>,
+    pub sort: QuerySort,
+    pub limit: Option<usize>,
+}
+
+impl QueryParams {
+    /// The filter of the matches, the bounds of the dates being compared as
+    /// strings once checked to be in the `YYYY-MM-DD` format
+    pub fn filter(&self) -> TodoResult<MatchFilter> {
+        for date in [&self.since, &self.until].into_iter().flatten() {
+            if !Annotation::is_date(date) {
+                return Err(Error::InvalidDate(date.clone()));
+            }
+        }
+
+        Ok(MatchFilter {
+            keywords: self.keywords.clone(),
+            author: self.author.clone(),
+            ticket: self.ticket.clone(),
+            since: self.since.clone(),
+            until: self.until.clone(),
+            text: self.text.clone(),
+        })
+    }
+
+    /// The globs of the paths, `None` when every path is accepted
+    pub fn path_globs(&self) -> TodoResult<Option<GlobSet>> {
+        if self.paths.is_empty() {
+            return Ok(None);
+        }
+
+        let invalid_glob = |e: globset::Error| Error::InvalidGlob(e.to_string());
+        let mut builder = GlobSetBuilder::new();
+        for path in &self.paths {
+            builder.add(Glob::new(path).map_err(invalid_glob)?);
+        }
+
+        builder.build().map(Some).map_err(invalid_glob)
+    }
+}
+
+/// Result of the `todo/query` request: the matches left after the limit and
+/// how many there were before it
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResponse {
+    pub total: usize,
+    pub items: Vec<ReportEntry>,
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn params_default_to_no_filter() {
+        let params = serde_json::from_value::<QueryParams>(json!({
+            "keywords": ["FIXME"],
+            "paths": ["src/**/*.rs"],
+            "sort": "date",
+            "limit": 10,
+        }))
+        .unwrap();
+
+        assert_eq!(params.keywords, vec!["FIXME".to_owned()]);
+        assert_eq!(params.sort, QuerySort::Date);
+        assert_eq!(params.limit, Some(10));
+        assert!(params.author.is_none());
+        assert_eq!(
+            serde_json::from_value::<QueryParams>(json!({}))
+                .unwrap()
+                .sort,
+            QuerySort::File
+        );
+    }
+
+    #[test]
+    fn paths_are_matched_against_globs() {
+        let params = QueryParams {
+            paths: vec!["src/**/*.rs".to_owned()],
+            ..Default::default()
+        };
+        let globs = params.path_globs().unwrap().unwrap();
+
+        assert!(globs.is_match("src/adapters/lsp.rs"));
+        assert!(!globs.is_match("tests/lsp.rs"));
+        assert!(QueryParams::default().path_globs().unwrap().is_none());
+        assert!(
+            QueryParams {
+                paths: vec!["src/[".to_owned()],
+                ..Default::default()
+            }
+            .path_globs()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn dates_have_to_be_in_the_iso_format() {
+        let with_dates = |since: &str, until: &str| QueryParams {
+            since: Some(since.to_owned()),
+            until: Some(until.to_owned()),
+            ..Default::default()
+        };
+
+        assert!(with_dates("2026-01-05", "2026-12-31").filter().is_ok());
+        assert!(with_dates("2026-1-5", "2026-12-31").filter().is_err());
+        assert!(with_dates("2026-01-05", "Jan 2026").filter().is_err());
+        assert!(QueryParams::default().filter().is_ok());
+    }
+
+    #[test]
+    fn undated_entries_sort_last() {
+        let dated = |file, line, date: &str| ReportEntry {
+            date: Some(date.to_owned()),
+            ..ReportEntry::for_test(file, line, "TODO", "")
+        };
+        let mut entries = [
+            ReportEntry::for_test("a.rs", 3, "TODO", ""),
+            dated("b.rs", 1, "2026-05-01"),
+            dated("a.rs", 1, "2026-01-01"),
+        ];
+        entries.sort_by(|a, b| QuerySort::Date.compare(a, b));
+
+        let order = entries
+            .iter()
+            .map(|e| (e.file.as_str(), e.line))
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![("a.rs", 1), ("b.rs", 1), ("a.rs", 3)]);
+    }
+}
+// grcov-excl-stop
@@ -1,13 +1,14 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use crate::{
-    entities::{FileState, State, TodoResult},
+    entities::{FileState, Match, Row, RowMetadata, State, TodoResult},
     use_cases::{
         Search as UseCase,
         ports::{RegexManager, Searcher},
     },
 };
 
+#[derive(Clone)]
 pub struct Search<T> {
     inner: UseCase<T>,
 }
@@ -29,17 +30,27 @@ impl<T: Searcher> Search<T> {
         self.inner.search_in_text(text)
     }
 
+    pub fn search_in_lines<'a>(
+        &self,
+        first_row: Row,
+        count: usize,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> BTreeMap<Row, (RowMetadata, Vec<Match>)> {
+        self.inner.search_in_lines(first_row, count, lines)
+    }
+
     pub fn recurssive_search<P: AsRef<Path>>(&self, root: P) -> State {
         self.inner.recurssive_search(root)
     }
+
+    pub fn should_skip<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.inner.should_skip(path)
+    }
 }
 
 impl<T: RegexManager> Search<T> {
     pub fn update_regex<S: AsRef<str>>(&mut self, key_words: &[S]) -> TodoResult<()> {
-        let key_words = key_words
-            .into_iter()
-            .map(|s| s.as_ref())
-            .collect::<Vec<_>>();
+        let key_words = key_words.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
 
         self.inner.update_regex(&key_words)
     }
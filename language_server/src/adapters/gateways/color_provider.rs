@@ -24,12 +24,19 @@ impl Colorer for ColorProvider {
     }
 
     fn color_text(&self, text: &str, color_type: ColorType) -> Option<Color> {
-        self.background.get(text).map(|config| match color_type {
-            ColorType::Background => config.background,
-        })
+        self.background
+            .get(text)
+            .and_then(|config| match color_type {
+                ColorType::Background => Some(config.background),
+                ColorType::Foreground => config.foreground,
+            })
     }
 
     fn update_palette(&mut self, text: String, colors: Colors) {
         self.background.insert(text, colors);
     }
+
+    fn clear_palette(&mut self) {
+        self.background.clear();
+    }
 }
@@ -1,2 +1,3 @@
 pub mod color_provider;
 pub mod ripgrep;
+pub mod tickets;
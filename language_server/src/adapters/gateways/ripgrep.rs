@@ -1,25 +1,118 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::BTreeMap, io, path::Path};
 
 use grep::{
     matcher::Matcher,
     regex::RegexMatcher,
-    searcher::{Searcher as GrepSearcher, sinks::UTF8},
+    searcher::{
+        Searcher as GrepSearcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkError,
+        SinkMatch,
+    },
 };
 use walkdir::WalkDir;
 
 use crate::{
-    entities::{Column, Error, FileState, Match, RowMetadata, State, TodoResult},
-    use_cases::ports::{RegexManager, RegexSearcher, Searcher},
+    entities::{Column, Error, FileState, Match, Row, RowMetadata, State, TodoResult, WideChars},
+    use_cases::{
+        CommentBlock, comment_start,
+        ports::{RegexManager, RegexSearcher, Searcher},
+    },
 };
 
+/// Most rows following a keyword row that are read to find the ones
+/// continuing its comment
+const MAX_CONTINUATION: usize = 64;
+
+type Rows = BTreeMap<Row, (RowMetadata, Vec<Match>)>;
+
+/// Collects the rows with matches, counting the rows which continue the
+/// comment of the last match of each of them
+struct RowSink<'s> {
+    searcher: &'s RipGrepSearcher,
+    rows: Rows,
+    /// The last row with matches and the comment it opened, while the rows
+    /// read since continue it
+    block: Option<(Row, CommentBlock)>,
+}
+
+impl<'s> RowSink<'s> {
+    fn new(searcher: &'s RipGrepSearcher) -> Self {
+        Self {
+            searcher,
+            rows: BTreeMap::new(),
+            block: None,
+        }
+    }
+
+    fn matched(&mut self, row: Row, line: &str) {
+        let (metadata, matches) = self.searcher.search_in_line(line);
+        self.block = matches
+            .last()
+            .and_then(|m| CommentBlock::open(line, m.column().column()))
+            .map(|block| (row, block));
+        self.rows.insert(row, (metadata, matches));
+    }
+
+    /// Reads a row without matches, returning whether it continues the
+    /// current comment
+    fn other(&mut self, row: Row, line: &str) -> bool {
+        let Some((block_row, block)) = &self.block else {
+            return false;
+        };
+        let Some((_, matches)) = self.rows.get_mut(block_row) else {
+            return false;
+        };
+        let Some(last) = matches.last_mut() else {
+            return false;
+        };
+
+        let next_row = block_row.row() + last.continuation() + 1;
+        if row.row() != next_row || !block.continues(line) {
+            self.block = None;
+            return false;
+        }
+        last.continue_comment();
+
+        true
+    }
+}
+
+impl Sink for RowSink<'_> {
+    type Error = io::Error;
+
+    fn matched(&mut self, _: &GrepSearcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        let line = std::str::from_utf8(mat.bytes()).map_err(io::Error::error_message)?;
+        let row = (mat.line_number().unwrap_or_default() as usize - 1).into();
+        RowSink::matched(self, row, line);
+
+        Ok(true)
+    }
+
+    fn context(&mut self, _: &GrepSearcher, ctx: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        if *ctx.kind() == SinkContextKind::After
+            && let Ok(line) = std::str::from_utf8(ctx.bytes())
+        {
+            let row = (ctx.line_number().unwrap_or_default() as usize - 1).into();
+            self.other(row, line);
+        }
+
+        Ok(true)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RipGrepSearcher {
     matcher: RegexMatcher,
 }
 
 impl RipGrepSearcher {
+    fn grep_searcher(&self) -> GrepSearcher {
+        SearcherBuilder::new()
+            .after_context(MAX_CONTINUATION)
+            .build()
+    }
+
     fn first_non_white_space_position(&self, text: &str) -> Column {
-        for (i, c) in text.chars().enumerate() {
+        for (i, c) in text.char_indices() {
             if !c.is_whitespace() {
                 return i.into();
             }
@@ -27,65 +120,75 @@ impl RipGrepSearcher {
 
         text.len().into()
     }
+
+    fn search_in_line(&self, line: &str) -> (RowMetadata, Vec<Match>) {
+        let content_end = line.trim_end_matches(['\n', '\r']).len();
+        let first_non_empty_col = self.first_non_white_space_position(line);
+        let metadata = RowMetadata::new(content_end, first_non_empty_col)
+            .with_wide_chars(WideChars::new(line));
+        let mut bounds = vec![];
+        let _ = self.matcher.try_find_iter(line.as_bytes(), |m| {
+            bounds.push((m.start(), m.end()));
+            Result::<bool, ()>::Ok(true)
+        });
+
+        let matches = bounds
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end))| {
+                let text_end = bounds.get(i + 1).map_or(content_end, |next| next.0);
+                let matched_patch = line[start..end].to_owned();
+                let comment_start = comment_start(&line[..start]).unwrap_or(start);
+
+                Match::new(start.into(), matched_patch, &line[end..text_end])
+                    .with_comment(comment_start.into(), content_end.into())
+            })
+            .collect();
+
+        (metadata, matches)
+    }
 }
 
 impl Searcher for RipGrepSearcher {
     fn search_in_path<P: AsRef<Path>>(&self, path: P) -> Option<FileState> {
-        let mut file_matches = HashMap::new();
-        let mut searcher = GrepSearcher::new();
-
-        let _ = searcher.search_path(
-            &self.matcher,
-            path,
-            UTF8(|line_num, line| {
-                let row = (line_num as usize - 1).into();
-                let first_non_empty_col = self.first_non_white_space_position(line);
-                let metadata = RowMetadata::new(line.len(), first_non_empty_col);
-                let mut matches = vec![];
-                let _ = self.matcher.try_find_iter(line.as_bytes(), |m| {
-                    let matched_patch = line[m.start()..m.end()].to_owned();
-                    let match_start = m.start().into();
-                    let m = Match::new(match_start, matched_patch);
-
-                    matches.push(m);
-                    Result::<bool, ()>::Ok(true)
-                });
-
-                file_matches.entry(row).insert_entry((metadata, matches));
-                Ok(true)
-            }),
-        );
+        let mut sink = RowSink::new(self);
+        let _ = self
+            .grep_searcher()
+            .search_path(&self.matcher, path, &mut sink);
 
-        FileState::try_new(file_matches)
+        FileState::try_new(sink.rows)
     }
 
     fn search_in_text(&self, text: &str) -> Option<FileState> {
-        let mut file_matches = HashMap::new();
-        let mut searcher = GrepSearcher::new();
-
-        let _ = searcher.search_slice(
-            &self.matcher,
-            text.as_bytes(),
-            UTF8(|line_num, line| {
-                let row = (line_num as usize - 1).into();
-                let first_non_empty_col = self.first_non_white_space_position(line);
-                let metadata = RowMetadata::new(line.len(), first_non_empty_col);
-                let mut matches = Vec::new();
-                let _ = self.matcher.try_find_iter(line.as_bytes(), |m| {
-                    let matched_patch = line[m.start()..m.end()].to_owned();
-                    let match_start = m.start().into();
-                    let m = Match::new(match_start, matched_patch);
-
-                    matches.push(m);
-                    Result::<bool, ()>::Ok(true)
-                });
-
-                file_matches.entry(row).insert_entry((metadata, matches));
-                Ok(true)
-            }),
-        );
+        let mut sink = RowSink::new(self);
+        let _ = self
+            .grep_searcher()
+            .search_slice(&self.matcher, text.as_bytes(), &mut sink);
 
-        FileState::try_new(file_matches)
+        FileState::try_new(sink.rows)
+    }
+
+    fn search_in_lines<'a>(
+        &self,
+        first_row: Row,
+        count: usize,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> Rows {
+        let mut sink = RowSink::new(self);
+
+        for (i, line) in lines.enumerate() {
+            let row = (first_row.row() + i).into();
+            let is_match = self.matcher.is_match(line.as_bytes()).unwrap_or(false);
+            let searched = i < count;
+
+            if is_match && searched {
+                sink.matched(row, line);
+            } else if is_match || !sink.other(row, line) && !searched {
+                break;
+            }
+        }
+
+        sink.rows
     }
 
     fn recurssive_search<P: AsRef<Path>>(&self, root: P) -> State {
@@ -149,7 +252,7 @@ impl RipGrepSearcher {
         T: AsRef<str>,
     {
         let regex = key_words
-            .into_iter()
+            .iter()
             .map(|k| k.as_ref())
             .collect::<Vec<_>>()
             .join("|");
@@ -162,6 +265,17 @@ impl RipGrepSearcher {
     }
 }
 
+#[cfg(test)]
+impl RipGrepSearcher {
+    /// The matches of the `TODO` keyword in `text`
+    pub(crate) fn todos_for_test(text: &str) -> FileState {
+        Self::try_from_key_words(&["TODO"])
+            .unwrap()
+            .search_in_text(text)
+            .unwrap()
+    }
+}
+
 impl RegexSearcher for RipGrepSearcher {}
 
 // grcov-excl-start
@@ -194,5 +308,156 @@ mod tests {
         assert_eq!((m1.start(), m1.end()), (2, 6));
         assert_eq!((m2.start(), m2.end()), (0, 5));
     }
+
+    #[test]
+    fn matches_carry_the_text_up_to_the_next_match() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO", "FIXME"]).unwrap();
+        let (_, matches) = searcher.search_in_line("// TODO: parse args FIXME the parser \n");
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].text(), ": parse args");
+        assert_eq!(matches[1].text(), "the parser");
+        assert_eq!(matches[0].summary(), "TODO: parse args");
+        assert_eq!(matches[1].summary(), "FIXME the parser");
+    }
+
+    #[test]
+    fn matches_know_their_extent() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+        let (_, matches) = searcher.search_in_line("let a = 1; // TODO (bob): later\r\n");
+        let m = &matches[0];
+
+        assert_eq!(m.column().column(), 14);
+        assert_eq!(m.end().column(), 18);
+        assert_eq!(m.text_start().column(), 19);
+        assert_eq!(m.annotation_end().map(|c| c.column()), Some(23));
+        assert_eq!((m.comment().0.column(), m.comment().1.column()), (11, 31));
+    }
+
+    #[test]
+    fn columns_are_byte_offsets_on_multi_byte_rows() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+        let (metadata, matches) = searcher.search_in_line("\u{3000}// 注意 TODO: 🦀\n");
+
+        assert_eq!(metadata.first_non_zero_pos().column(), 3);
+        assert_eq!(matches[0].column().column(), 13);
+        assert_eq!(
+            metadata
+                .wide_chars()
+                .iter()
+                .map(|(c, _)| c.column())
+                .collect::<Vec<_>>(),
+            vec![0, 6, 9, 19]
+        );
+    }
+
+    #[test]
+    fn references_are_found_on_the_keyword_and_the_annotation() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["FIXME"]).unwrap();
+        let (_, matches) = searcher.search_in_line("// FIXME(perf, #12): slow\n");
+        let m = &matches[0];
+
+        assert_eq!(m.reference_at(4.into()), Some("#12"));
+        assert_eq!(m.reference_at(10.into()), Some("perf"));
+        assert_eq!(m.reference_at(16.into()), Some("#12"));
+        assert_eq!(m.reference_at(22.into()), None);
+    }
+
+    #[test]
+    fn matches_are_grouped_by_keyword_in_row_order() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO", "FIXME"]).unwrap();
+        let text = "// FIXME first\n\n// TODO second\n// FIXME third TODO fourth\n";
+        let file_state = searcher.search_in_text(text).unwrap();
+        let groups = file_state
+            .matches_by_keyword()
+            .into_iter()
+            .map(|(keyword, matches)| {
+                let rows = matches.iter().map(|(row, _)| row.row()).collect::<Vec<_>>();
+                (keyword, rows)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups, vec![("FIXME", vec![0, 3]), ("TODO", vec![2, 3])]);
+    }
+
+    #[test]
+    fn splicing_shifts_the_rows_after_an_edit() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+        let text = "// TODO a\n// TODO b\nc\n// TODO d\n";
+        let mut file_state = searcher.search_in_text(text).unwrap();
+
+        // Row 1 is replaced by three rows, of which only the last one matches
+        file_state.splice(1.into()..2.into(), 3);
+        for (row, (metadata, matches)) in
+            searcher.search_in_lines(1.into(), 3, ["x\n", "y\n", "// TODO z\n"].into_iter())
+        {
+            file_state.replace(row, metadata, matches);
+        }
+
+        let rows = file_state
+            .matches()
+            .map(|(row, m)| (row.row(), m.text().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                (0, "a".to_owned()),
+                (3, "z".to_owned()),
+                (5, "d".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn row_length_excludes_the_line_terminator() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+
+        // Rows are split on LF, a lone CR is only a terminator ending the text
+        for text in ["// TODO x\n", "// TODO x\r\n", "// TODO x\r", "// TODO x"] {
+            let file_state = searcher.search_in_text(text).unwrap();
+            let metadata = file_state.metadata(0.into()).unwrap();
+
+            assert_eq!(*metadata.content_len(), 9, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn rows_continuing_a_comment_are_counted() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO", "FIXME"]).unwrap();
+        let text = "fn main() {\n    // TODO: parse\n    //   the args\n    //   twice\n    // done\n    // FIXME: x\n    //   y\n    // TODO: z\n}\n";
+        let file_state = searcher.search_in_text(text).unwrap();
+        let continuations = file_state
+            .matches()
+            .map(|(row, m)| (row.row(), *m.continuation()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(continuations, vec![(1, 2), (5, 1), (7, 0)]);
+    }
+
+    #[test]
+    fn searching_lines_reads_on_while_the_comment_continues() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+        let lines = [
+            "// TODO: a\n",
+            "//   b\n",
+            "//   c\n",
+            "x\n",
+            "// TODO: d\n",
+        ];
+        let rows = searcher.search_in_lines(0.into(), 1, lines.into_iter());
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(*rows[&Row::from(0)].1[0].continuation(), 2);
+    }
+
+    #[test]
+    fn searching_lines_keeps_only_the_rows_with_matches() {
+        let searcher = RipGrepSearcher::try_from_key_words(&["TODO"]).unwrap();
+        let lines = ["let a = 1;\n", "// TODO: b\n", "\n", "// TODO: d"];
+        let rows = searcher.search_in_lines(10.into(), lines.len(), lines.into_iter());
+
+        assert_eq!(rows.keys().map(Row::row).collect::<Vec<_>>(), vec![11, 13]);
+        assert_eq!(rows[&Row::from(13)].1[0].text(), ": d");
+    }
 }
 // grcov-excl-stop
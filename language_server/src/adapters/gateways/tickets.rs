@@ -0,0 +1,124 @@
+use grep::{
+    matcher::{Captures, Matcher},
+    regex::RegexMatcher,
+};
+
+use crate::{
+    adapters::config::TicketPattern,
+    entities::{Error, TodoResult},
+};
+
+/// Finds the ticket references of a text and builds their URL from the
+/// configured patterns
+#[derive(Debug, Clone, Default)]
+pub struct TicketLinks {
+    patterns: Vec<(RegexMatcher, String)>,
+}
+
+impl TicketLinks {
+    pub fn try_new(patterns: &[TicketPattern]) -> TodoResult<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let matcher = RegexMatcher::new(&pattern.regex)
+                    .map_err(|e| Error::InvalidRegex(e.to_string()))?;
+                Ok((matcher, pattern.url.clone()))
+            })
+            .collect::<TodoResult<_>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// The references found in `text`, as byte offsets and URL, ordered by
+    /// offset. A reference matched by several patterns links to the first
+    /// one.
+    pub fn links(&self, text: &str) -> Vec<(usize, usize, String)> {
+        let mut links: Vec<(usize, usize, String)> = vec![];
+
+        for (matcher, url) in &self.patterns {
+            let Ok(mut captures) = matcher.new_captures() else {
+                continue;
+            };
+            let _ = matcher.captures_iter(text.as_bytes(), &mut captures, |captures| {
+                let Some(m) = captures.get(0) else {
+                    return true;
+                };
+                if links
+                    .iter()
+                    .any(|(start, end, _)| m.start() < *end && *start < m.end())
+                {
+                    return true;
+                }
+
+                let mut target = vec![];
+                captures.interpolate(
+                    |name| matcher.capture_index(name),
+                    text.as_bytes(),
+                    url.as_bytes(),
+                    &mut target,
+                );
+                links.push((
+                    m.start(),
+                    m.end(),
+                    String::from_utf8_lossy(&target).into_owned(),
+                ));
+
+                true
+            });
+        }
+        links.sort_by_key(|(start, _, _)| *start);
+
+        links
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(regex: &str, url: &str) -> TicketPattern {
+        TicketPattern {
+            regex: regex.to_owned(),
+            url: url.to_owned(),
+        }
+    }
+
+    #[test]
+    fn capture_groups_are_substituted() {
+        let tickets = TicketLinks::try_new(&[
+            pattern(r"PROJ-(\d+)", "https://tracker/browse/PROJ-$1"),
+            pattern(r"#(\d+)", "https://github.com/org/repo/issues/$1"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            tickets.links("(#12) fix PROJ-345 then PROJ-6"),
+            vec![
+                (1, 4, "https://github.com/org/repo/issues/12".to_owned()),
+                (10, 18, "https://tracker/browse/PROJ-345".to_owned()),
+                (24, 30, "https://tracker/browse/PROJ-6".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_pattern_wins_on_overlaps() {
+        let tickets = TicketLinks::try_new(&[
+            pattern(r"GH-(\d+)", "https://github.com/org/repo/issues/$1"),
+            pattern(r"[A-Z]+-(\d+)", "https://tracker/browse/$0"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            tickets.links("GH-78"),
+            vec![(0, 5, "https://github.com/org/repo/issues/78".to_owned())]
+        );
+    }
+
+    #[test]
+    fn invalid_regexes_are_rejected() {
+        assert!(TicketLinks::try_new(&[pattern("(", "")]).is_err());
+    }
+}
+// grcov-excl-stop
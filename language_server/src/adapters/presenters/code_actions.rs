@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use tower_lsp_server::ls_types::{
+    self, CodeAction, CodeActionKind, CodeActionOrCommand, Range, TextEdit, Uri, WorkspaceEdit,
+};
+
+use crate::{
+    adapters::{
+        config::Config,
+        presenters::{Encoder, PositionPresenter},
+    },
+    entities::{Match, Position, Row, RowMetadata},
+    use_cases::{ports::Conversion, today, user_name},
+};
+
+/// Whether the comment holding the match on `row` overlaps `range`
+pub fn overlaps(encoder: Encoder, range: &Range, row: Row, m: &Match) -> bool {
+    let span = encoder.row_range(row, *m.column(), m.comment().1);
+    let as_pair = |p: ls_types::Position| (p.line, p.character);
+
+    as_pair(range.start) <= as_pair(span.end) && as_pair(span.start) <= as_pair(range.end)
+}
+
+/// A code action applying a single edit to the document
+fn edit_action(uri: &Uri, title: String, range: Range, new_text: String) -> CodeActionOrCommand {
+    let edit = TextEdit { range, new_text };
+
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+/// Actions offered on a match: switching to another keyword, removing its
+/// comment and adding the author and date missing from its annotation
+pub fn code_actions(
+    uri: &Uri,
+    config: &Config,
+    encoder: Encoder,
+    row: Row,
+    row_meta: &RowMetadata,
+    m: &Match,
+) -> Vec<CodeActionOrCommand> {
+    let keyword = m.keyword();
+    let mut other_keywords = config
+        .highlights
+        .keys()
+        .filter(|k| *k != keyword)
+        .collect::<Vec<_>>();
+    other_keywords.sort();
+
+    let mut actions = other_keywords
+        .into_iter()
+        .map(|other| {
+            let title = format!("Change {keyword} to {other}");
+            edit_action(uri, title, encoder.keyword_range(row, m), other.clone())
+        })
+        .collect::<Vec<_>>();
+
+    let (comment_start, comment_end) = *m.comment();
+    let removal = if comment_start == *row_meta.first_non_zero_pos() {
+        // The comment is the whole line, so the line goes away with it
+        let line_start = Position::new(row, 0.into());
+        let next_line_start = Position::new((row.row() + 1).into(), 0.into());
+
+        Range {
+            start: PositionPresenter::convert(line_start),
+            end: PositionPresenter::convert(next_line_start),
+        }
+    } else {
+        encoder.row_range(row, comment_start, comment_end)
+    };
+    actions.push(edit_action(
+        uri,
+        format!("Remove this {keyword} comment"),
+        removal,
+        String::new(),
+    ));
+
+    let annotation = m.annotation();
+    let (user, date) = (user_name(), today());
+    let missing_metadata = [
+        annotation.author().is_none().then_some(user),
+        annotation.date().is_none().then_some(date),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if !missing_metadata.is_empty() {
+        let (column, new_text) = match m.annotation_end() {
+            Some(annotation_end) if annotation.is_empty() => {
+                (annotation_end, missing_metadata.join(", "))
+            }
+            Some(annotation_end) => (annotation_end, format!(", {}", missing_metadata.join(", "))),
+            None => (*m.end(), format!("({})", missing_metadata.join(", "))),
+        };
+        actions.push(edit_action(
+            uri,
+            "Add author/date metadata".to_owned(),
+            encoder.row_range(row, column, column),
+            new_text,
+        ));
+    }
+
+    actions
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{gateways::ripgrep::RipGrepSearcher, presenters::PositionEncoding};
+
+    /// The actions offered on the first match of `text`
+    fn actions(text: &str) -> Vec<CodeAction> {
+        let file_state = RipGrepSearcher::todos_for_test(text);
+        let encoder = Encoder::new(PositionEncoding::Utf16, &file_state);
+        let (row, (row_meta, matches)) = file_state.rows().iter().next().unwrap();
+        let uri = Uri::from_file_path("/a.rs").unwrap();
+
+        code_actions(
+            &uri,
+            &Config::default(),
+            encoder,
+            *row,
+            row_meta,
+            &matches[0],
+        )
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => Some(action),
+            CodeActionOrCommand::Command(_) => None,
+        })
+        .collect()
+    }
+
+    /// The range removed by the "Remove this TODO comment" action of the first
+    /// match of `line`
+    fn removed_range(line: &str) -> Range {
+        let uri = Uri::from_file_path("/a.rs").unwrap();
+
+        actions(line)
+            .into_iter()
+            .find(|action| action.title.starts_with("Remove"))
+            .and_then(|action| action.edit?.changes?.remove(&uri)?.pop())
+            .map(|edit| edit.range)
+            .unwrap()
+    }
+
+    #[test]
+    fn actions_only_insert_known_metadata() {
+        let titles = |text| {
+            actions(text)
+                .into_iter()
+                .map(|action| action.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            titles("// TODO a\n"),
+            vec!["Remove this TODO comment", "Add author/date metadata"]
+        );
+        assert_eq!(
+            titles("// TODO(alice, #12, 2026-01-01): a\n"),
+            vec!["Remove this TODO comment"]
+        );
+    }
+
+    #[test]
+    fn removing_a_trailing_comment_keeps_the_code() {
+        for line in [
+            r#"let u = "https://x"; // TODO a"#,
+            "i--; // TODO a",
+            "#[cfg(test)] // TODO a",
+        ] {
+            let range = removed_range(line);
+            let comment = line.find("// TODO").unwrap() as u32;
+
+            assert_eq!(
+                (range.start, range.end),
+                (
+                    ls_types::Position::new(0, comment),
+                    ls_types::Position::new(0, line.len() as u32)
+                ),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn removing_a_whole_line_comment_removes_the_line() {
+        for text in [
+            "    // TODO a\nfn main() {}\n",
+            "// see #12 TODO a\nfn main() {}\n",
+            "# step #2 TODO a\nmain()\n",
+        ] {
+            let range = removed_range(text);
+
+            assert_eq!(
+                (range.start, range.end),
+                (ls_types::Position::new(0, 0), ls_types::Position::new(1, 0)),
+                "{text:?}"
+            );
+        }
+    }
+}
+// grcov-excl-stop
@@ -0,0 +1,45 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use tower_lsp_server::ls_types::{Diagnostic, NumberOrString};
+
+use crate::{
+    adapters::{
+        config::Config,
+        presenters::{Encoder, SeverityPresenter},
+    },
+    use_cases::ports::Conversion,
+};
+
+/// Source of the diagnostics, and identifier of their pull provider
+pub const DIAGNOSTIC_SOURCE: &str = "todo-highlight";
+
+/// Builds one diagnostic per keyword match of the file, skipping the keywords
+/// the user opted out of
+pub fn diagnostics(config: &Config, encoder: Encoder) -> Vec<Diagnostic> {
+    encoder
+        .file_state()
+        .matches()
+        .filter_map(|(row, m)| {
+            let severity = config.severity(m.keyword())?;
+
+            Some(Diagnostic {
+                range: encoder.keyword_range(row, m),
+                severity: Some(SeverityPresenter::convert(severity)),
+                code: Some(NumberOrString::String(m.keyword().to_owned())),
+                source: Some(DIAGNOSTIC_SOURCE.to_owned()),
+                message: m.summary(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Identifies a set of diagnostics, so that unchanged ones are not sent again
+pub fn result_id(diagnostics: &[Diagnostic]) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(diagnostics)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+
+    format!("{:016x}", hasher.finish())
+}
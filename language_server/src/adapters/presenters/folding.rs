@@ -0,0 +1,19 @@
+use tower_lsp_server::ls_types::{FoldingRange, FoldingRangeKind};
+
+use crate::entities::FileState;
+
+/// Folds the comments spanning several rows, from the row of their keyword
+pub fn folding_ranges(file_state: &FileState) -> Vec<FoldingRange> {
+    file_state
+        .matches()
+        .filter(|(_, m)| *m.continuation() > 0)
+        .map(|(row, m)| FoldingRange {
+            start_line: row.row() as u32,
+            start_character: None,
+            end_line: (row.row() + m.continuation()) as u32,
+            end_character: None,
+            kind: Some(FoldingRangeKind::Comment),
+            collapsed_text: Some(m.summary()),
+        })
+        .collect()
+}
@@ -0,0 +1,36 @@
+use crate::{
+    adapters::config::Config,
+    entities::{Match, State},
+};
+
+/// Markdown shown when hovering a keyword: its description, the metadata
+/// parsed from the match and how often the keyword occurs in the workspace
+pub fn hover_contents(config: &Config, state: &State, m: &Match) -> String {
+    let mut sections = vec![];
+
+    match config.description(m.keyword()) {
+        Some(description) => sections.push(format!("**{}**: {description}", m.keyword())),
+        None => sections.push(format!("**{}**", m.keyword())),
+    }
+
+    let annotation = m.annotation();
+    if !annotation.is_empty() {
+        let metadata = [
+            ("Author", annotation.author()),
+            ("Ticket", annotation.ticket()),
+            ("Date", annotation.date()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("- {name}: `{value}`")))
+        .collect::<Vec<_>>();
+        sections.push(metadata.join("\n"));
+    }
+
+    let others = state.count(m.keyword()).saturating_sub(1);
+    sections.push(match others {
+        1 => format!("1 other `{}` in the workspace", m.keyword()),
+        n => format!("{n} other `{}`s in the workspace", m.keyword()),
+    });
+
+    sections.join("\n\n")
+}
@@ -0,0 +1,28 @@
+use tower_lsp_server::ls_types::DocumentLink;
+
+use crate::adapters::{gateways::tickets::TicketLinks, presenters::Encoder};
+
+/// Links of the ticket references found in the text of the matches
+pub fn document_links(tickets: &TicketLinks, encoder: Encoder) -> Vec<DocumentLink> {
+    encoder
+        .file_state()
+        .matches()
+        .flat_map(|(row, m)| {
+            let text_start = m.text_start().column();
+
+            tickets
+                .links(m.text())
+                .into_iter()
+                .map(move |(start, end, url)| DocumentLink {
+                    range: encoder.row_range(
+                        row,
+                        (text_start + start).into(),
+                        (text_start + end).into(),
+                    ),
+                    tooltip: Some(url.clone()),
+                    target: url.parse().ok(),
+                    data: None,
+                })
+        })
+        .collect()
+}
@@ -1,5 +1,27 @@
+pub mod code_actions;
 pub mod color;
+pub mod diagnostics;
+pub mod folding;
+pub mod hover;
+pub mod links;
 pub mod position;
+pub mod report;
+pub mod semantic_tokens;
+pub mod severity;
+pub mod symbols;
+pub mod target;
 
+pub use code_actions::{code_actions, overlaps};
 pub use color::Color as ColorPresenter;
-pub use position::Position as PositionPresenter;
+pub use diagnostics::{DIAGNOSTIC_SOURCE, diagnostics, result_id};
+pub use folding::folding_ranges;
+pub use hover::hover_contents;
+pub use links::document_links;
+pub use position::{Encoder, Position as PositionPresenter, PositionEncoding};
+pub use report::{MarkdownReport as MarkdownReportPresenter, ReportEntry, keyword_summary};
+pub use semantic_tokens::{
+    SemanticTokens as SemanticTokensPresenter, semantic_tokens, semantic_tokens_options,
+};
+pub use severity::Severity as SeverityPresenter;
+pub use symbols::document_symbols;
+pub use target::{Target, mention_ranges, rename_edits, target_at, target_ranges};
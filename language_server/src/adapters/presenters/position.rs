@@ -1,6 +1,9 @@
-use tower_lsp_server::ls_types;
+use tower_lsp_server::ls_types::{self, PositionEncodingKind, Range};
 
-use crate::{entities, use_cases::ports::Conversion};
+use crate::{
+    entities::{self, Column, FileState, Match, Row, WideChars},
+    use_cases::ports::Conversion,
+};
 
 pub struct Position;
 
@@ -17,3 +20,205 @@ impl Conversion for Position {
         }
     }
 }
+
+/// Unit in which the client counts the characters of a position, the columns
+/// of the entities being byte offsets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// The first of the encodings offered by the client, UTF-16 when it offers
+    /// none of them
+    pub fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        offered
+            .unwrap_or_default()
+            .iter()
+            .find_map(|kind| match kind.as_str() {
+                "utf-8" => Some(Self::Utf8),
+                "utf-16" => Some(Self::Utf16),
+                "utf-32" => Some(Self::Utf32),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    fn units(self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Counts the byte offset `column` of a row in this encoding
+    pub fn encode(self, column: Column, wide_chars: &WideChars) -> Column {
+        let shift = wide_chars
+            .iter()
+            .take_while(|(start, _)| start.column() < column.column())
+            .map(|(_, c)| c.len_utf8() - self.units(c))
+            .sum::<usize>();
+
+        column.column().saturating_sub(shift).into()
+    }
+
+    /// Byte offset of a column of a row counted in this encoding. A column
+    /// falling inside a character is moved to the start of that character.
+    pub fn decode(self, column: Column, wide_chars: &WideChars) -> Column {
+        let column = column.column();
+        let mut shift = 0;
+
+        for (start, c) in wide_chars.iter() {
+            let encoded_start = start.column() - shift;
+            if encoded_start >= column {
+                break;
+            }
+            if column < encoded_start + self.units(c) {
+                return start;
+            }
+            shift += c.len_utf8() - self.units(c);
+        }
+
+        (column + shift).into()
+    }
+}
+
+/// Turns the byte columns of the rows of a file into LSP positions, counted
+/// in the encoding negotiated with the client
+#[derive(Clone, Copy)]
+pub struct Encoder<'a> {
+    encoding: PositionEncoding,
+    file_state: &'a FileState,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(encoding: PositionEncoding, file_state: &'a FileState) -> Self {
+        Self {
+            encoding,
+            file_state,
+        }
+    }
+
+    pub fn file_state(&self) -> &'a FileState {
+        self.file_state
+    }
+
+    pub fn column(&self, row: Row, column: Column) -> Column {
+        self.file_state.metadata(row).map_or(column, |metadata| {
+            self.encoding.encode(column, metadata.wide_chars())
+        })
+    }
+
+    pub fn position(&self, row: Row, column: Column) -> ls_types::Position {
+        Position::convert(entities::Position::new(row, self.column(row, column)))
+    }
+
+    /// The range between two columns of the same row
+    pub fn row_range(&self, row: Row, start: Column, end: Column) -> Range {
+        Range {
+            start: self.position(row, start),
+            end: self.position(row, end),
+        }
+    }
+
+    /// The range covered by the keyword of a match
+    pub fn keyword_range(&self, row: Row, m: &Match) -> Range {
+        self.row_range(row, *m.column(), *m.end())
+    }
+
+    /// The range covered by the keyword of a match and its annotation, e.g.
+    /// `TODO(#123)`
+    pub fn annotated_range(&self, row: Row, m: &Match) -> Range {
+        let end = m
+            .annotation_end()
+            .map_or(*m.end(), |end| (end.column() + 1).into());
+
+        self.row_range(row, *m.column(), end)
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `é` takes 2 bytes and 1 UTF-16 unit, `🦀` 4 bytes and 2 UTF-16 units
+    const LINE: &str = "// é TODO 🦀 FIXME";
+
+    fn encode(encoding: PositionEncoding, column: usize) -> usize {
+        encoding
+            .encode(column.into(), &WideChars::new(LINE))
+            .column()
+    }
+
+    fn decode(encoding: PositionEncoding, column: usize) -> usize {
+        encoding
+            .decode(column.into(), &WideChars::new(LINE))
+            .column()
+    }
+
+    #[test]
+    fn negotiation_picks_the_first_supported_encoding() {
+        let offered = [
+            PositionEncodingKind::new("utf-7"),
+            PositionEncodingKind::UTF32,
+            PositionEncodingKind::UTF8,
+        ];
+
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&offered)),
+            PositionEncoding::Utf32
+        );
+        assert_eq!(PositionEncoding::negotiate(None), PositionEncoding::Utf16);
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&[])),
+            PositionEncoding::Utf16
+        );
+    }
+
+    #[test]
+    fn columns_are_counted_in_the_negotiated_encoding() {
+        let todo = LINE.find("TODO").unwrap();
+        let fixme = LINE.find("FIXME").unwrap();
+
+        assert_eq!((todo, fixme), (6, 16));
+        assert_eq!(encode(PositionEncoding::Utf8, fixme), 16);
+        assert_eq!(encode(PositionEncoding::Utf16, todo), 5);
+        assert_eq!(encode(PositionEncoding::Utf16, fixme), 13);
+        assert_eq!(encode(PositionEncoding::Utf32, fixme), 12);
+    }
+
+    #[test]
+    fn encoded_columns_are_decoded_back_to_bytes() {
+        for encoding in [
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf32,
+        ] {
+            for (column, _) in LINE.char_indices() {
+                assert_eq!(decode(encoding, encode(encoding, column)), column);
+            }
+            assert_eq!(decode(encoding, encode(encoding, LINE.len())), LINE.len());
+        }
+    }
+
+    #[test]
+    fn columns_inside_a_character_move_to_its_start() {
+        // The second UTF-16 unit of `🦀`
+        assert_eq!(decode(PositionEncoding::Utf16, 11), 11);
+        assert_eq!(decode(PositionEncoding::Utf8, 13), 11);
+    }
+}
+// grcov-excl-stop
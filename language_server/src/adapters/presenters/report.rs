@@ -0,0 +1,119 @@
+use serde::Serialize;
+
+use crate::{
+    adapters::presenters::Encoder,
+    entities::{FileState, Match, Row},
+    use_cases::ports::Conversion,
+};
+
+/// A match listed by the `todo.listAll` and `todo.exportReport` commands
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub file: String,
+    pub line: usize,
+    pub character: usize,
+    pub keyword: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl ReportEntry {
+    /// Entry listing the match on `row` of `file`
+    pub fn new(file: String, encoder: Encoder, row: Row, m: &Match) -> Self {
+        Self {
+            file,
+            line: row.row(),
+            character: encoder.column(row, *m.column()).column(),
+            keyword: m.keyword().clone(),
+            text: m.text().clone(),
+            author: m.annotation().author().clone(),
+            ticket: m.annotation().ticket().clone(),
+            date: m.annotation().date().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ReportEntry {
+    /// An entry at the start of `line`, without annotation
+    pub(crate) fn for_test(file: &str, line: usize, keyword: &str, text: &str) -> Self {
+        Self {
+            file: file.to_owned(),
+            line,
+            character: 0,
+            keyword: keyword.to_owned(),
+            text: text.to_owned(),
+            author: None,
+            ticket: None,
+            date: None,
+        }
+    }
+}
+
+/// One line summary of the keywords of a file, e.g. `3 TODO · 1 FIXME`
+pub fn keyword_summary(file_state: &FileState) -> String {
+    file_state
+        .matches_by_keyword()
+        .into_iter()
+        .map(|(keyword, matches)| format!("{} {keyword}", matches.len()))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Markdown report of the matches, grouped by keyword. Lines are 1-based.
+pub struct MarkdownReport;
+
+impl Conversion for MarkdownReport {
+    type From = Vec<ReportEntry>;
+    type To = String;
+
+    fn convert(from: Self::From) -> Self::To {
+        let mut keywords = from.iter().map(|e| e.keyword.as_str()).collect::<Vec<_>>();
+        keywords.sort();
+        keywords.dedup();
+
+        let mut report = "# TODO report\n".to_owned();
+        for keyword in keywords {
+            let entries = from.iter().filter(|e| e.keyword == keyword);
+            report.push_str(&format!("\n## {keyword} ({})\n\n", entries.clone().count()));
+
+            for entry in entries {
+                report.push_str(&format!("- `{}:{}`", entry.file, entry.line + 1));
+                if !entry.text.is_empty() {
+                    report.push_str(&format!(" {}", entry.text));
+                }
+                report.push('\n');
+            }
+        }
+
+        report
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_are_grouped_by_keyword() {
+        let report = MarkdownReport::convert(vec![
+            ReportEntry::for_test("src/main.rs", 0, "TODO", "parse args"),
+            ReportEntry::for_test("src/lib.rs", 9, "FIXME", ""),
+            ReportEntry::for_test("src/lib.rs", 11, "TODO", "cache"),
+        ]);
+
+        assert_eq!(
+            report,
+            "# TODO report\n\
+             \n## FIXME (1)\n\n- `src/lib.rs:10`\n\
+             \n## TODO (2)\n\n- `src/main.rs:1` parse args\n- `src/lib.rs:12` cache\n"
+        );
+    }
+}
+// grcov-excl-stop
@@ -0,0 +1,128 @@
+use std::ops::RangeInclusive;
+
+use tower_lsp_server::ls_types::{
+    self, SemanticToken, SemanticTokenType, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions,
+};
+
+use crate::{
+    adapters::presenters::Encoder,
+    entities::{self, Position, Row},
+    use_cases::ports::Conversion,
+};
+
+pub struct SemanticTokens;
+
+impl Conversion for SemanticTokens {
+    /// Start, length and token type index of every token, sorted by position
+    type From = Vec<(entities::Position, usize, u32)>;
+    type To = Vec<ls_types::SemanticToken>;
+
+    fn convert(from: Self::From) -> Self::To {
+        let mut previous = (0, 0);
+
+        from.into_iter()
+            .map(|(position, length, token_type)| {
+                let (row, column) = position.into_pair();
+                let (line, start) = (row.row() as u32, column.column() as u32);
+                let (previous_line, previous_start) = previous;
+                let delta_start = if line == previous_line {
+                    start - previous_start
+                } else {
+                    start
+                };
+                previous = (line, start);
+
+                ls_types::SemanticToken {
+                    delta_line: line - previous_line,
+                    delta_start,
+                    length: length as u32,
+                    token_type,
+                    token_modifiers_bitset: 0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Semantic tokens of the keywords of a file matched in the `rows` range, the
+/// token type of a keyword being its index in `token_types`. Keywords left out
+/// of the file's configuration are `None`.
+pub fn semantic_tokens(
+    token_types: &[Option<&str>],
+    encoder: Encoder,
+    rows: RangeInclusive<Row>,
+) -> Vec<SemanticToken> {
+    let tokens = encoder
+        .file_state()
+        .matches()
+        .filter(|(row, _)| rows.contains(row))
+        .filter_map(|(row, m)| {
+            let token_type = token_types
+                .iter()
+                .position(|t| *t == Some(m.keyword().as_str()))?;
+            let start = encoder.column(row, *m.column());
+            let end = encoder.column(row, *m.end());
+
+            Some((
+                Position::new(row, start),
+                end.column() - start.column(),
+                token_type as u32,
+            ))
+        })
+        .collect();
+
+    SemanticTokens::convert(tokens)
+}
+
+/// Semantic tokens of the whole file or of a range, with a token type per
+/// keyword of `token_types`, named after the lowercased keyword
+pub fn semantic_tokens_options(token_types: &[String]) -> SemanticTokensOptions {
+    SemanticTokensOptions {
+        legend: SemanticTokensLegend {
+            token_types: token_types
+                .iter()
+                .map(|keyword| SemanticTokenType::from(keyword.to_lowercase()))
+                .collect(),
+            token_modifiers: vec![],
+        },
+        range: Some(true),
+        full: Some(SemanticTokensFullOptions::Bool(true)),
+        ..Default::default()
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{gateways::ripgrep::RipGrepSearcher, presenters::PositionEncoding};
+
+    #[test]
+    fn tokens_are_delta_encoded() {
+        let tokens = vec![
+            (Position::new(1.into(), 3.into()), 4, 0),
+            (Position::new(1.into(), 12.into()), 5, 1),
+            (Position::new(4.into(), 2.into()), 4, 0),
+        ];
+        let encoded = SemanticTokens::convert(tokens)
+            .into_iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length, t.token_type))
+            .collect::<Vec<_>>();
+
+        assert_eq!(encoded, vec![(1, 3, 4, 0), (0, 9, 5, 1), (3, 2, 4, 0)]);
+    }
+
+    #[test]
+    fn only_keywords_of_the_legend_become_tokens() {
+        let file_state = RipGrepSearcher::todos_for_test("// TODO a\n");
+        let encoder = Encoder::new(PositionEncoding::Utf16, &file_state);
+        let rows = Row::from(0)..=Row::from(usize::MAX);
+
+        let tokens = semantic_tokens(&[None, Some("TODO")], encoder, rows.clone());
+        assert_eq!(tokens.len(), 1);
+        assert_eq!((tokens[0].length, tokens[0].token_type), (4, 1));
+        assert!(semantic_tokens(&[None], encoder, rows).is_empty());
+    }
+}
+// grcov-excl-stop
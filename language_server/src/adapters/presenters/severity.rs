@@ -0,0 +1,19 @@
+use tower_lsp_server::ls_types;
+
+use crate::{entities, use_cases::ports::Conversion};
+
+pub struct Severity;
+
+impl Conversion for Severity {
+    type From = entities::Severity;
+    type To = ls_types::DiagnosticSeverity;
+
+    fn convert(from: Self::From) -> Self::To {
+        match from {
+            entities::Severity::Error => Self::To::ERROR,
+            entities::Severity::Warning => Self::To::WARNING,
+            entities::Severity::Information => Self::To::INFORMATION,
+            entities::Severity::Hint => Self::To::HINT,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use tower_lsp_server::ls_types::{DocumentSymbol, Range, SymbolKind};
+
+use crate::adapters::presenters::Encoder;
+
+/// Builds the outline of a file: one symbol per keyword, each holding the
+/// matches of that keyword sorted by row
+pub fn document_symbols(encoder: Encoder) -> Vec<DocumentSymbol> {
+    encoder
+        .file_state()
+        .matches_by_keyword()
+        .into_iter()
+        .map(|(keyword, matches)| {
+            let children = matches
+                .into_iter()
+                .map(|(row, m)| {
+                    let range = encoder.keyword_range(row, m);
+
+                    #[allow(deprecated)]
+                    DocumentSymbol {
+                        name: m.summary(),
+                        detail: None,
+                        kind: SymbolKind::STRING,
+                        tags: None,
+                        deprecated: None,
+                        range,
+                        selection_range: range,
+                        children: None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            let first = children
+                .first()
+                .expect("Every keyword group has at least one match")
+                .range;
+            let last = children
+                .last()
+                .expect("Every keyword group has at least one match")
+                .range;
+
+            #[allow(deprecated)]
+            DocumentSymbol {
+                name: keyword.to_owned(),
+                detail: Some(children.len().to_string()),
+                kind: SymbolKind::KEY,
+                tags: None,
+                deprecated: None,
+                range: Range {
+                    start: first.start,
+                    end: last.end,
+                },
+                selection_range: first,
+                children: Some(children),
+            }
+        })
+        .collect()
+}
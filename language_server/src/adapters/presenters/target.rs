@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use tower_lsp_server::ls_types::{Range, TextEdit, Uri};
+
+use crate::{
+    adapters::presenters::{Encoder, PositionEncoding},
+    entities::{Column, Row, State},
+};
+
+/// What a position designates: a keyword, or a ticket or tag of an annotation
+pub enum Target<'a> {
+    Keyword(&'a str),
+    Reference(&'a str),
+}
+
+/// The target at the given position, with the range it covers
+pub fn target_at(encoder: Encoder<'_>, row: Row, column: Column) -> Option<(Target<'_>, Range)> {
+    let m = encoder.file_state().annotated_match_at(row, column)?;
+    if m.contains(column) {
+        return Some((Target::Keyword(m.keyword()), encoder.keyword_range(row, m)));
+    }
+
+    let reference = m.reference_at(column)?;
+    let (start, end) = m
+        .reference_spans(reference)
+        .into_iter()
+        .find(|(start, end)| (*start..=*end).contains(&column))?;
+
+    Some((
+        Target::Reference(reference),
+        encoder.row_range(row, start, end),
+    ))
+}
+
+/// Ranges of a file covered by `target`: the occurrences of the keyword, or
+/// the tickets and tags of the annotations equal to the reference
+pub fn target_ranges(encoder: Encoder, target: &Target) -> Vec<Range> {
+    encoder
+        .file_state()
+        .matches()
+        .flat_map(|(row, m)| match target {
+            Target::Keyword(keyword) if m.keyword() == keyword => {
+                vec![encoder.keyword_range(row, m)]
+            }
+            Target::Keyword(_) => vec![],
+            Target::Reference(reference) => m
+                .reference_spans(reference)
+                .into_iter()
+                .map(|(start, end)| encoder.row_range(row, start, end))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Ranges of a file mentioning `reference`: the items of the annotations equal
+/// to it and its occurrences in the text of the matches
+pub fn mention_ranges(encoder: Encoder, reference: &str) -> Vec<Range> {
+    encoder
+        .file_state()
+        .matches()
+        .flat_map(|(row, m)| {
+            m.mention_spans(reference)
+                .into_iter()
+                .map(move |(start, end)| encoder.row_range(row, start, end))
+        })
+        .collect()
+}
+
+/// Edits renaming `target` to `new_name` in every file of the state
+pub fn rename_edits(
+    encoding: PositionEncoding,
+    state: &State,
+    target: &Target,
+    new_name: &str,
+) -> HashMap<Uri, Vec<TextEdit>> {
+    state
+        .iter()
+        .filter_map(|(file_name, file_state)| {
+            let edits = target_ranges(Encoder::new(encoding, file_state), target)
+                .into_iter()
+                .map(|range| TextEdit {
+                    range,
+                    new_text: new_name.to_owned(),
+                })
+                .collect::<Vec<_>>();
+
+            if edits.is_empty() {
+                return None;
+            }
+
+            Some((Uri::from_file_path(file_name)?, edits))
+        })
+        .collect()
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::gateways::ripgrep::RipGrepSearcher;
+
+    #[test]
+    fn tickets_are_highlighted_in_annotations_and_text() {
+        let file_state = RipGrepSearcher::todos_for_test(
+            "// TODO(#12): see #12, not #123\n// TODO: after #12\n",
+        );
+        let encoder = Encoder::new(PositionEncoding::Utf16, &file_state);
+        let (target, _) = target_at(encoder, 0.into(), 9.into()).unwrap();
+        let Target::Reference(reference) = target else {
+            panic!("#12 is a ticket");
+        };
+
+        let spans = mention_ranges(encoder, reference)
+            .into_iter()
+            .map(|range| (range.start.line, range.start.character, range.end.character))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 8, 11), (0, 18, 21), (1, 15, 18)]);
+    }
+}
+// grcov-excl-stop
@@ -0,0 +1,190 @@
+use getset::Getters;
+
+/// Metadata attached to a keyword through the `KEYWORD(author, #ticket, date)`
+/// convention, e.g. `TODO(alice, #123, 2026-12-01): ...`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Getters)]
+pub struct Annotation {
+    /// Who is responsible for the keyword, the first item that is neither a
+    /// ticket nor a date
+    #[getset(get = "pub")]
+    author: Option<String>,
+    /// Ticket reference, either `#123` or `PROJ-123`
+    #[getset(get = "pub")]
+    ticket: Option<String>,
+    /// Date in the `YYYY-MM-DD` format
+    #[getset(get = "pub")]
+    date: Option<String>,
+    /// Every item that is neither a ticket nor a date, e.g. `perf` in
+    /// `FIXME(perf)`, the author included
+    #[getset(get = "pub")]
+    tags: Vec<String>,
+}
+
+impl Annotation {
+    /// Parses the annotation from the text following a keyword. Text that does
+    /// not start with a parenthesized list has no annotation.
+    pub fn parse(text: &str) -> Self {
+        let mut annotation = Self::default();
+        let Some(items) = text
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .map(|(items, _)| items)
+        else {
+            return annotation;
+        };
+
+        for item in items.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            if annotation.ticket.is_none() && Self::is_ticket(item) {
+                annotation.ticket = Some(item.to_owned());
+            } else if annotation.date.is_none() && Self::is_date(item) {
+                annotation.date = Some(item.to_owned());
+            } else {
+                let tag = item.trim_start_matches('@').to_owned();
+                annotation.author.get_or_insert_with(|| tag.clone());
+                annotation.tags.push(tag);
+            }
+        }
+
+        annotation
+    }
+
+    /// The items of the annotation starting `text`, parentheses and `@`
+    /// excluded, with their byte offsets
+    pub fn items(text: &str) -> Vec<(usize, usize, &str)> {
+        let Some((items, _)) = text.strip_prefix('(').and_then(|rest| rest.split_once(')')) else {
+            return vec![];
+        };
+        let mut start = 1;
+
+        items
+            .split(',')
+            .filter_map(|item| {
+                let item_start = start;
+                start += item.len() + 1;
+
+                let trimmed = item.trim_start();
+                let offset = item_start + item.len() - trimmed.len();
+                let item = trimmed.trim_end();
+                let tag = item.trim_start_matches('@');
+                let offset = offset + item.len() - tag.len();
+
+                (!tag.is_empty()).then_some((offset, offset + tag.len(), tag))
+            })
+            .collect()
+    }
+
+    /// The item of the annotation starting `text` found at byte `offset`
+    pub fn item_at(text: &str, offset: usize) -> Option<&str> {
+        Self::items(text)
+            .into_iter()
+            .find(|(start, end, _)| (*start..=*end).contains(&offset))
+            .map(|(_, _, item)| item)
+    }
+
+    /// Whether the annotation refers to `reference`, either as its ticket or
+    /// as one of its tags
+    pub fn refers_to(&self, reference: &str) -> bool {
+        self.ticket.as_deref() == Some(reference) || self.tags.iter().any(|tag| tag == reference)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none() && self.ticket.is_none() && self.date.is_none()
+    }
+
+    fn is_ticket(item: &str) -> bool {
+        let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+        match item.strip_prefix('#') {
+            Some(number) => is_number(number),
+            None => item.split_once('-').is_some_and(|(project, number)| {
+                !project.is_empty()
+                    && project.chars().all(|c| c.is_ascii_alphanumeric())
+                    && project.starts_with(|c: char| c.is_ascii_uppercase())
+                    && is_number(number)
+            }),
+        }
+    }
+
+    /// Whether `item` is a date in the `YYYY-MM-DD` format
+    pub fn is_date(item: &str) -> bool {
+        let parts = item.split('-').collect::<Vec<_>>();
+
+        parts.len() == 3
+            && parts
+                .iter()
+                .zip([4, 2, 2])
+                .all(|(part, len)| part.len() == len && part.chars().all(|c| c.is_ascii_digit()))
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_without_parentheses_has_no_annotation() {
+        assert!(Annotation::parse(": fix the parser").is_empty());
+        assert!(Annotation::parse("").is_empty());
+        assert!(Annotation::parse("(alice: unterminated").is_empty());
+    }
+
+    #[test]
+    fn full_annotation_is_parsed() {
+        let annotation = Annotation::parse("(alice, #123, 2026-12-01): fix the parser");
+
+        assert_eq!(annotation.author().as_deref(), Some("alice"));
+        assert_eq!(annotation.ticket().as_deref(), Some("#123"));
+        assert_eq!(annotation.date().as_deref(), Some("2026-12-01"));
+    }
+
+    #[test]
+    fn annotation_items_can_come_in_any_order() {
+        let annotation = Annotation::parse("(2026-01-31, PROJ-42, @bob)");
+
+        assert_eq!(annotation.author().as_deref(), Some("bob"));
+        assert_eq!(annotation.ticket().as_deref(), Some("PROJ-42"));
+        assert_eq!(annotation.date().as_deref(), Some("2026-01-31"));
+    }
+
+    #[test]
+    fn items_other_than_ticket_and_date_are_tags() {
+        let annotation = Annotation::parse("(perf, @alice, #9)");
+
+        assert_eq!(annotation.author().as_deref(), Some("perf"));
+        assert_eq!(annotation.tags(), &["perf", "alice"]);
+        assert!(annotation.refers_to("alice"));
+        assert!(annotation.refers_to("#9"));
+        assert!(!annotation.refers_to("#90"));
+    }
+
+    #[test]
+    fn item_at_offset_is_found() {
+        let text = "(perf, @alice): slow";
+
+        assert_eq!(Annotation::item_at(text, 0), None);
+        assert_eq!(Annotation::item_at(text, 1), Some("perf"));
+        assert_eq!(Annotation::item_at(text, 5), Some("perf"));
+        assert_eq!(Annotation::item_at(text, 9), Some("alice"));
+        assert_eq!(Annotation::item_at(text, 16), None);
+    }
+
+    #[test]
+    fn items_know_their_offsets() {
+        assert_eq!(
+            Annotation::items("( perf , @alice): slow"),
+            vec![(2, 6, "perf"), (10, 15, "alice")]
+        );
+        assert!(Annotation::items(": slow").is_empty());
+    }
+
+    #[test]
+    fn partial_annotation_is_parsed() {
+        let annotation = Annotation::parse("(#7)");
+
+        assert_eq!(annotation.author(), &None);
+        assert_eq!(annotation.ticket().as_deref(), Some("#7"));
+        assert_eq!(annotation.date(), &None);
+    }
+}
+// grcov-excl-stop
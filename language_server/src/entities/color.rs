@@ -3,6 +3,7 @@ use typed_builder::TypedBuilder;
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum ColorType {
     Background,
+    Foreground,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -29,10 +30,16 @@ impl Color {
 #[derive(Debug, TypedBuilder)]
 pub struct Colors {
     pub background: Color,
+    /// Text color, only rendered by clients through semantic tokens
+    #[builder(default)]
+    pub foreground: Option<Color>,
 }
 
 impl Colors {
-    pub fn new(background: Color) -> Self {
-        Self { background }
+    pub fn new(background: Color, foreground: Option<Color>) -> Self {
+        Self {
+            background,
+            foreground,
+        }
     }
 }
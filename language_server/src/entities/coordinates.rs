@@ -25,7 +25,7 @@ impl From<usize> for Column {
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Row(usize);
 
 impl Row {
@@ -0,0 +1,132 @@
+use std::ops::Range;
+
+use crate::entities::{Position, Row};
+
+/// Text of a document opened in the editor, kept line by line so that an
+/// edit only touches the lines it covers. Every line keeps its terminator.
+#[derive(Debug, Default, Clone)]
+pub struct Document {
+    lines: Vec<String>,
+}
+
+impl Document {
+    pub fn new(text: &str) -> Self {
+        Self {
+            lines: text.split_inclusive('\n').map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.concat()
+    }
+
+    pub fn line(&self, row: Row) -> Option<&str> {
+        self.lines.get(row.row()).map(String::as_str)
+    }
+
+    pub fn lines(&self, rows: Range<Row>) -> impl Iterator<Item = &str> {
+        let end = rows.end.row().min(self.lines.len());
+        let start = rows.start.row().min(end);
+
+        self.lines[start..end].iter().map(String::as_str)
+    }
+
+    /// Replaces the text between `start` and `end`, whose columns are byte
+    /// offsets, by `text`. Returns the rows the edit removed and how many
+    /// rows replaced them.
+    pub fn edit(&mut self, start: Position, end: Position, text: &str) -> (Range<Row>, usize) {
+        let (start_row, start_col) = start.into_pair();
+        let (end_row, end_col) = end.into_pair();
+        let first = start_row.row().min(self.lines.len());
+        let last = end_row.row().max(first).min(self.lines.len());
+
+        let prefix = self
+            .line(first.into())
+            .map_or("", |line| &line[..start_col.column().min(line.len())]);
+        let suffix = self
+            .line(last.into())
+            .map_or("", |line| &line[end_col.column().min(line.len())..]);
+        let replaced = format!("{prefix}{text}{suffix}");
+
+        // The line following the replaced ones is merged in when the edit
+        // removes the terminator of the last replaced line
+        let merged = !replaced.is_empty() && !replaced.ends_with('\n');
+        let removed_end = (last + 1 + usize::from(merged)).min(self.lines.len());
+        let replaced = match self.line(removed_end.saturating_sub(1).into()) {
+            Some(next) if merged && removed_end > last + 1 => format!("{replaced}{next}"),
+            _ => replaced,
+        };
+
+        let new_lines = Document::new(&replaced).lines;
+        let inserted = new_lines.len();
+        let removed_end = removed_end.max(first);
+        self.lines.splice(first..removed_end, new_lines);
+
+        (first.into()..removed_end.into(), inserted)
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(row: usize, column: usize) -> Position {
+        Position::new(row.into(), column.into())
+    }
+
+    #[test]
+    fn lines_keep_their_terminator() {
+        let document = Document::new("a\r\nb\nc");
+
+        assert_eq!(document.line(0.into()), Some("a\r\n"));
+        assert_eq!(document.line(2.into()), Some("c"));
+        assert_eq!(document.text(), "a\r\nb\nc");
+    }
+
+    #[test]
+    fn editing_inside_a_line_touches_only_that_line() {
+        let mut document = Document::new("a\n// TODO\nc\n");
+        let edited = document.edit(position(1, 3), position(1, 7), "FIXME");
+
+        assert_eq!(edited, (1.into()..2.into(), 1));
+        assert_eq!(document.text(), "a\n// FIXME\nc\n");
+    }
+
+    #[test]
+    fn inserting_lines_shifts_the_following_ones() {
+        let mut document = Document::new("a\nb\n");
+        let edited = document.edit(position(1, 0), position(1, 0), "x\ny\n");
+
+        assert_eq!(edited, (1.into()..2.into(), 3));
+        assert_eq!(document.text(), "a\nx\ny\nb\n");
+    }
+
+    #[test]
+    fn removing_a_terminator_merges_lines() {
+        let mut document = Document::new("a\nb\nc\n");
+        let edited = document.edit(position(0, 1), position(1, 0), "");
+
+        assert_eq!(edited, (0.into()..2.into(), 1));
+        assert_eq!(document.text(), "ab\nc\n");
+    }
+
+    #[test]
+    fn appending_after_the_last_line() {
+        let mut document = Document::new("a\n");
+        let edited = document.edit(position(1, 0), position(1, 0), "b");
+
+        assert_eq!(edited, (1.into()..1.into(), 1));
+        assert_eq!(document.text(), "a\nb");
+    }
+
+    #[test]
+    fn removing_everything() {
+        let mut document = Document::new("a\nb");
+        let edited = document.edit(position(0, 0), position(1, 1), "");
+
+        assert_eq!(edited, (0.into()..2.into(), 0));
+        assert_eq!(document.text(), "");
+    }
+}
+// grcov-excl-stop
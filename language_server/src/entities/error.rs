@@ -1,6 +1,8 @@
 #[derive(Debug)]
 pub enum Error {
     InvalidRegex(String),
+    InvalidGlob(String),
+    InvalidDate(String),
 }
 
 pub type TodoResult<T> = Result<T, Error>;
@@ -1,39 +1,162 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, ops::Range};
 
 use getset::Getters;
 
-use crate::entities::{Column, Row, RowMetadata};
+use crate::entities::{Annotation, Column, Row, RowMetadata};
 
 /// Represents a match of one of the user-defined keywords inside a row
-#[derive(Debug, Getters)]
+#[derive(Debug, Clone, Getters)]
 pub struct Match {
     /// The column inside the row where the match starts
     #[getset(get = "pub")]
     column: Column,
+    /// The column inside the row right after the keyword
+    #[getset(get = "pub")]
+    end: Column,
     /// User configures keyword that triggered the match
     #[getset(get = "pub")]
     keyword: String,
+    /// The trimmed text following the keyword, up to the next match or the
+    /// end of the row
+    #[getset(get = "pub")]
+    text: String,
+    /// The column inside the row where the trimmed text starts
+    #[getset(get = "pub")]
+    text_start: Column,
+    /// Metadata parsed from the text, e.g. `TODO(alice, #123): ...`
+    #[getset(get = "pub")]
+    annotation: Annotation,
+    /// Columns delimiting the comment holding the match, from the comment
+    /// marker to the end of the row content
+    #[getset(get = "pub")]
+    comment: (Column, Column),
+    /// Number of rows following the one of the match which continue its
+    /// comment
+    #[getset(get = "pub")]
+    continuation: usize,
 }
 
 impl Match {
-    pub fn new(column: Column, matched_patch: String) -> Self {
+    /// Builds a match from the keyword and the `segment` of the row following
+    /// it. Until told otherwise, the comment spans the keyword and its text.
+    pub fn new(column: Column, matched_patch: String, segment: &str) -> Self {
+        let end = column.column() + matched_patch.len();
+        let trimmed = segment.trim_start();
+        let text_start = end + segment.len() - trimmed.len();
+        let text = trimmed.trim_end().to_owned();
+
         Self {
             column,
+            end: end.into(),
             keyword: matched_patch,
+            annotation: Annotation::parse(&text),
+            comment: (column, (text_start + text.len()).into()),
+            text_start: text_start.into(),
+            text,
+            continuation: 0,
+        }
+    }
+
+    pub fn with_comment(mut self, start: Column, end: Column) -> Self {
+        self.comment = (start, end);
+        self
+    }
+
+    /// Counts one more row continuing the comment of the match
+    pub fn continue_comment(&mut self) {
+        self.continuation += 1;
+    }
+
+    /// Whether `column` falls on the keyword of the match
+    pub fn contains(&self, column: Column) -> bool {
+        (self.column.column()..=self.end.column()).contains(&column.column())
+    }
+
+    /// The column of the parenthesis closing the annotation, if the text
+    /// starts with one, e.g. `TODO(alice): ...`
+    pub fn annotation_end(&self) -> Option<Column> {
+        if !self.text.starts_with('(') {
+            return None;
+        }
+
+        self.text
+            .find(')')
+            .map(|offset| (self.text_start.column() + offset).into())
+    }
+
+    /// The ticket or tag of the annotation found at `column`. On the keyword,
+    /// the ticket, or the first tag without ticket.
+    pub fn reference_at(&self, column: Column) -> Option<&str> {
+        if self.contains(column) {
+            return self
+                .annotation
+                .ticket()
+                .as_deref()
+                .or_else(|| self.annotation.tags().first().map(String::as_str));
+        }
+
+        let offset = column.column().checked_sub(self.text_start.column())?;
+        Annotation::item_at(&self.text, offset)
+    }
+
+    /// Columns of the items of the annotation equal to `reference`
+    pub fn reference_spans(&self, reference: &str) -> Vec<(Column, Column)> {
+        let text_start = self.text_start.column();
+
+        Annotation::items(&self.text)
+            .into_iter()
+            .filter(|(_, _, item)| *item == reference)
+            .map(|(start, end, _)| ((text_start + start).into(), (text_start + end).into()))
+            .collect()
+    }
+
+    /// Columns of `reference` in the text of the match: the items of the
+    /// annotation equal to it and its mentions in the rest of the text, e.g.
+    /// both tickets of `TODO(#12): see #12`
+    pub fn mention_spans(&self, reference: &str) -> Vec<(Column, Column)> {
+        let text_start = self.text_start.column();
+        let body_start = self
+            .annotation_end()
+            .map_or(text_start, |end| end.column() + 1);
+        let body = &self.text[body_start - text_start..];
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+        let mut spans = self.reference_spans(reference);
+        spans.extend(
+            body.match_indices(reference)
+                .filter(|(offset, _)| {
+                    !body[..*offset].ends_with(is_word)
+                        && !body[offset + reference.len()..].starts_with(is_word)
+                })
+                .map(|(offset, _)| {
+                    let start = body_start + offset;
+                    (start.into(), (start + reference.len()).into())
+                }),
+        );
+
+        spans
+    }
+
+    /// Human readable summary of the match: the keyword followed by its text
+    pub fn summary(&self) -> String {
+        match self.text.chars().next() {
+            None => self.keyword.clone(),
+            Some(c) if c.is_alphanumeric() => format!("{} {}", self.keyword, self.text),
+            Some(_) => format!("{}{}", self.keyword, self.text),
         }
     }
 }
 
 /// Encapsulates all the matches inside a file
-#[derive(Debug, Default, Getters)]
+#[derive(Debug, Default, Clone, Getters)]
 pub struct FileState {
-    /// Container for organizing matches by row
+    /// Container for organizing matches by row, ordered by row
     #[getset(get = "pub")]
-    rows: HashMap<Row, (RowMetadata, Vec<Match>)>,
+    rows: BTreeMap<Row, (RowMetadata, Vec<Match>)>,
 }
 
 impl FileState {
-    pub fn try_new(data: HashMap<Row, (RowMetadata, Vec<Match>)>) -> Option<Self> {
+    pub fn try_new(data: BTreeMap<Row, (RowMetadata, Vec<Match>)>) -> Option<Self> {
         (!data.is_empty()).then_some(Self { rows: data })
     }
 
@@ -41,8 +164,82 @@ impl FileState {
         self.rows.insert(row, (metadata, new_matches));
     }
 
-    #[allow(clippy::len_without_is_empty)]
+    /// Iterates over every match of the file together with its row, sorted
+    /// by row and column
+    pub fn matches(&self) -> impl Iterator<Item = (Row, &Match)> {
+        self.rows
+            .iter()
+            .flat_map(|(row, (_, matches))| matches.iter().map(move |m| (*row, m)))
+    }
+
+    /// Metadata of `row`, if it holds matches
+    pub fn metadata(&self, row: Row) -> Option<&RowMetadata> {
+        self.rows.get(&row).map(|(metadata, _)| metadata)
+    }
+
+    /// The match whose keyword covers the given position
+    pub fn match_at(&self, row: Row, column: Column) -> Option<&Match> {
+        self.rows
+            .get(&row)
+            .and_then(|(_, matches)| matches.iter().find(|m| m.contains(column)))
+    }
+
+    /// The match whose keyword or annotation covers the given position
+    pub fn annotated_match_at(&self, row: Row, column: Column) -> Option<&Match> {
+        self.rows.get(&row).and_then(|(_, matches)| {
+            matches.iter().find(|m| {
+                let end = m.annotation_end().unwrap_or(*m.end());
+                (m.column().column()..=end.column()).contains(&column.column())
+            })
+        })
+    }
+
+    /// Groups the matches of the file by keyword, keywords being ordered by
+    /// their first occurrence and matches by row
+    pub fn matches_by_keyword(&self) -> Vec<(&str, Vec<(Row, &Match)>)> {
+        let mut groups: Vec<(&str, Vec<(Row, &Match)>)> = vec![];
+
+        for (row, m) in self.matches() {
+            match groups
+                .iter_mut()
+                .find(|(keyword, _)| *keyword == m.keyword().as_str())
+            {
+                Some((_, matches)) => matches.push((row, m)),
+                None => groups.push((m.keyword(), vec![(row, m)])),
+            }
+        }
+
+        groups
+    }
+
+    /// Drops the rows `removed` by an edit and shifts the following ones so
+    /// that they start `inserted` rows after the start of the edit
+    pub fn splice(&mut self, removed: Range<Row>, inserted: usize) {
+        let following = self.rows.split_off(&removed.start);
+        let shift = |row: Row| row.row() - removed.end.row() + removed.start.row() + inserted;
+
+        self.rows.extend(
+            following
+                .into_iter()
+                .filter(|(row, _)| *row >= removed.end)
+                .map(|(row, data)| (shift(row).into(), data)),
+        );
+    }
+
+    /// Keeps only the matches for which `keep` returns true, dropping the rows
+    /// left without matches
+    pub fn retain(&mut self, mut keep: impl FnMut(&Match) -> bool) {
+        self.rows.retain(|_, (_, matches)| {
+            matches.retain(&mut keep);
+            !matches.is_empty()
+        });
+    }
+
     pub fn len(&self) -> usize {
         self.rows.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
 }
@@ -2,19 +2,63 @@ use getset::Getters;
 
 use crate::entities::Column;
 
-#[derive(Debug, Getters)]
+/// Characters of a row taking more than one byte, with the column they start
+/// at, from which a column can be counted in other units than bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WideChars(Vec<(usize, char)>);
+
+impl WideChars {
+    pub fn new(line: &str) -> Self {
+        Self(line.char_indices().filter(|(_, c)| !c.is_ascii()).collect())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Column, char)> + '_ {
+        self.0.iter().map(|&(column, c)| (column.into(), c))
+    }
+}
+
+#[derive(Debug, Clone, Getters)]
 pub struct RowMetadata {
+    /// Length of the row without its terminator, LF or CRLF. Rows are split
+    /// on LF only, so a lone CR is only stripped at the end of the last row.
     #[getset(get = "pub")]
-    line_len: usize,
+    content_len: usize,
     #[getset(get = "pub")]
     first_non_zero_pos: Column,
+    #[getset(get = "pub")]
+    wide_chars: WideChars,
 }
 
 impl RowMetadata {
-    pub fn new(line_len: usize, first_non_zero_pos: Column) -> Self {
+    pub fn new(content_len: usize, first_non_zero_pos: Column) -> Self {
         Self {
-            line_len,
+            content_len,
             first_non_zero_pos,
+            wide_chars: WideChars::default(),
         }
     }
+
+    pub fn with_wide_chars(mut self, wide_chars: WideChars) -> Self {
+        self.wide_chars = wide_chars;
+        self
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_chars_start_at_byte_columns() {
+        let wide_chars = WideChars::new("// TODO: café 🦀 ok");
+        let columns = wide_chars
+            .iter()
+            .map(|(column, c)| (column.column(), c))
+            .collect::<Vec<_>>();
+
+        assert_eq!(columns, vec![(12, 'é'), (15, '🦀')]);
+        assert_eq!(WideChars::new("// TODO: ascii"), WideChars::default());
+    }
 }
+// grcov-excl-stop
@@ -1,15 +1,21 @@
+pub mod annotation;
 pub mod color;
 pub mod coordinates;
+pub mod document;
 pub mod error;
 pub mod file_matches;
 pub mod metadata;
 pub mod position;
+pub mod severity;
 pub mod state;
 
+pub use annotation::*;
 pub use color::*;
 pub use coordinates::*;
+pub use document::*;
 pub use error::*;
 pub use file_matches::*;
 pub use metadata::*;
 pub use position::*;
+pub use severity::*;
 pub use state::*;
@@ -0,0 +1,8 @@
+/// How loudly a keyword match is reported to the editor as a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
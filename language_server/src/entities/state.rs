@@ -1,10 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    path::Path,
+};
 
-use crate::entities::FileState;
+use crate::entities::{FileState, Match, Row, RowMetadata};
 
+/// Matches of the workspace files, made of the files as they are on disk and
+/// an overlay of the documents opened in the editor, which shadows them
 #[derive(Debug, Default)]
 pub struct State {
     inner: HashMap<String, FileState>,
+    /// Matches of the open documents, `None` for a document without matches
+    overlay: HashMap<String, Option<FileState>>,
 }
 
 impl State {
@@ -24,14 +32,224 @@ impl State {
         let mut inner = HashMap::with_capacity(1);
         inner.insert(file_name, matches);
 
-        Self { inner }
+        Self {
+            inner,
+            ..Default::default()
+        }
     }
 
     pub fn get(&self, file_name: &str) -> Option<&FileState> {
-        self.inner.get(file_name)
+        match self.overlay.get(file_name) {
+            Some(overlaid) => overlaid.as_ref(),
+            None => self.inner.get(file_name),
+        }
     }
 
+    /// Sets the matches of an open document, shadowing its content on disk
+    pub fn overlay(&mut self, file_name: String, matches: Option<FileState>) {
+        self.overlay.insert(file_name, matches);
+    }
+
+    /// Drops the overlay of a document, its content on disk being visible
+    /// again
+    pub fn close(&mut self, file_name: &str) {
+        let _ = self.overlay.remove(file_name);
+    }
+
+    /// Makes the matches of an open document those of its content on disk,
+    /// once the document is saved. The document stays open.
+    pub fn promote(&mut self, file_name: &str) {
+        match self.overlay.get(file_name).cloned() {
+            Some(Some(matches)) => self.insert(file_name.to_owned(), matches),
+            Some(None) => self.remove(file_name),
+            None => {}
+        }
+    }
+
+    /// Removes a file from the disk layer
     pub fn remove(&mut self, file_name: &str) {
         let _ = self.inner.remove(file_name);
     }
+
+    /// Applies an edit of an open document: the rows `removed` are replaced
+    /// by `inserted` rows, whose matches are `rows`
+    pub fn splice(
+        &mut self,
+        file_name: &str,
+        removed: Range<Row>,
+        inserted: usize,
+        rows: BTreeMap<Row, (RowMetadata, Vec<Match>)>,
+    ) {
+        let overlaid = self.overlay.entry(file_name.to_owned()).or_default();
+        let Some(file_state) = overlaid else {
+            *overlaid = FileState::try_new(rows);
+            return;
+        };
+
+        file_state.splice(removed, inserted);
+        for (row, (metadata, matches)) in rows {
+            file_state.replace(row, metadata, matches);
+        }
+        if file_state.is_empty() {
+            *overlaid = None;
+        }
+    }
+
+    /// Keeps only the matches for which `keep` returns true, dropping the
+    /// files left without matches
+    pub fn retain_matches(&mut self, mut keep: impl FnMut(&str, &Match) -> bool) {
+        self.inner.retain(|file_name, file_state| {
+            file_state.retain(|m| keep(file_name, m));
+            !file_state.is_empty()
+        });
+        for (file_name, overlaid) in &mut self.overlay {
+            if let Some(file_state) = overlaid {
+                file_state.retain(|m| keep(file_name, m));
+                if file_state.is_empty() {
+                    *overlaid = None;
+                }
+            }
+        }
+    }
+
+    /// Moves the files at `from`, or below it for a directory, to `to`.
+    /// Returns the previous and new names of the moved files.
+    pub fn rename(&mut self, from: &str, to: &str) -> Vec<(String, String)> {
+        let renamed = |file_name: &str| {
+            let relative = Path::new(file_name).strip_prefix(from).ok()?;
+            let new_name = match relative.as_os_str().is_empty() {
+                true => to.to_owned(),
+                false => Path::new(to).join(relative).to_string_lossy().into_owned(),
+            };
+
+            Some((file_name.to_owned(), new_name))
+        };
+        let mut moved = self
+            .inner
+            .keys()
+            .chain(self.overlay.keys())
+            .filter_map(|file_name| renamed(file_name))
+            .collect::<Vec<_>>();
+        moved.sort();
+        moved.dedup();
+
+        for (old_name, new_name) in &moved {
+            if let Some(file_state) = self.inner.remove(old_name) {
+                self.inner.insert(new_name.clone(), file_state);
+            }
+            if let Some(overlaid) = self.overlay.remove(old_name) {
+                self.overlay.insert(new_name.clone(), overlaid);
+            }
+        }
+
+        moved
+    }
+
+    /// Removes from the disk layer the files for which `keep` returns false,
+    /// returning their names
+    pub fn retain_files(&mut self, mut keep: impl FnMut(&str) -> bool) -> Vec<String> {
+        let removed = self
+            .inner
+            .keys()
+            .filter(|file_name| !keep(file_name))
+            .cloned()
+            .collect::<Vec<_>>();
+        removed.iter().for_each(|file_name| self.remove(file_name));
+
+        removed
+    }
+
+    /// Number of matches of `keyword` across all the files
+    pub fn count(&self, keyword: &str) -> usize {
+        self.iter()
+            .flat_map(|(_, file_state)| file_state.matches())
+            .filter(|(_, m)| m.keyword() == keyword)
+            .count()
+    }
+
+    /// Iterates over the files with matches, open documents taking
+    /// precedence over their content on disk
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FileState)> {
+        let overlaid = self.overlay.iter().filter_map(|(file_name, matches)| {
+            matches
+                .as_ref()
+                .map(|matches| (file_name.as_str(), matches))
+        });
+        let on_disk = self
+            .inner
+            .iter()
+            .filter(|(file_name, _)| !self.overlay.contains_key(*file_name))
+            .map(|(file_name, matches)| (file_name.as_str(), matches));
+
+        overlaid.chain(on_disk)
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_state(keyword: &str) -> FileState {
+        let m = Match::new(0.into(), keyword.to_owned(), "");
+        let rows = BTreeMap::from([(Row::from(0), (RowMetadata::new(4, 0.into()), vec![m]))]);
+
+        FileState::try_new(rows).unwrap()
+    }
+
+    fn keyword(state: &State) -> Option<&str> {
+        state
+            .get("a.rs")
+            .and_then(|file_state| file_state.matches().next())
+            .map(|(_, m)| m.keyword().as_str())
+    }
+
+    #[test]
+    fn open_documents_shadow_the_disk() {
+        let mut state = State::from_file_matches("a.rs".to_owned(), file_state("TODO"));
+
+        state.overlay("a.rs".to_owned(), Some(file_state("FIXME")));
+        assert_eq!(keyword(&state), Some("FIXME"));
+        assert_eq!((state.count("TODO"), state.count("FIXME")), (0, 1));
+
+        state.overlay("a.rs".to_owned(), None);
+        assert_eq!(keyword(&state), None);
+        assert_eq!(state.iter().count(), 0);
+    }
+
+    #[test]
+    fn closing_restores_the_disk() {
+        let mut state = State::from_file_matches("a.rs".to_owned(), file_state("TODO"));
+        state.overlay("a.rs".to_owned(), Some(file_state("FIXME")));
+
+        state.close("a.rs");
+        assert_eq!(keyword(&state), Some("TODO"));
+    }
+
+    #[test]
+    fn renaming_a_directory_moves_its_files() {
+        let mut state = State::from_file_matches("/ws/src/a.rs".to_owned(), file_state("TODO"));
+        state.insert("/ws/srcs/b.rs".to_owned(), file_state("TODO"));
+
+        let moved = state.rename("/ws/src", "/ws/lib");
+
+        assert_eq!(
+            moved,
+            vec![("/ws/src/a.rs".to_owned(), "/ws/lib/a.rs".to_owned())]
+        );
+        assert!(state.get("/ws/src/a.rs").is_none());
+        assert!(state.get("/ws/lib/a.rs").is_some());
+        assert!(state.get("/ws/srcs/b.rs").is_some());
+    }
+
+    #[test]
+    fn saving_promotes_the_overlay() {
+        let mut state = State::from_file_matches("a.rs".to_owned(), file_state("TODO"));
+        state.overlay("a.rs".to_owned(), Some(file_state("FIXME")));
+
+        state.promote("a.rs");
+        state.close("a.rs");
+        assert_eq!(keyword(&state), Some("FIXME"));
+    }
 }
+// grcov-excl-stop
@@ -0,0 +1,217 @@
+/// Markers starting a comment in the most common languages
+const COMMENT_MARKERS: [&str; 6] = ["//", "/*", "#", "--", ";;", "<!--"];
+
+/// Byte offset of the first comment marker of `line`, the text preceding a
+/// keyword, if any.
+///
+/// Markers inside string literals are skipped, e.g. the `//` of a URL, and so
+/// are `#`, `--` and `;;` glued to the code before them, like `i--` or `#[`,
+/// which are operators or attributes rather than comments. Block comments
+/// closed on the line are skipped as well, code following them.
+///
+/// The detection is language agnostic: without any marker, a line whose first
+/// non-whitespace character is `*` is considered the continuation of a block
+/// comment.
+pub fn comment_start(line: &str) -> Option<usize> {
+    let mut start = 0;
+    while let Some(rest) = line.get(start..).filter(|rest| !rest.is_empty()) {
+        if let Some(len) = literal_len(rest).or_else(|| closed_block_len(rest)) {
+            start += len;
+            continue;
+        }
+        if is_marker(&line[..start], rest) {
+            return Some(start);
+        }
+        start += rest.chars().next().map_or(1, char::len_utf8);
+    }
+
+    let trimmed = line.trim_start();
+    (trimmed.starts_with('*') && !trimmed.starts_with("*/")).then(|| line.len() - trimmed.len())
+}
+
+/// Whether `rest` starts with a comment marker, `before` being the text of the
+/// line preceding it
+fn is_marker(before: &str, rest: &str) -> bool {
+    let after_code = before.ends_with(|c: char| !c.is_whitespace());
+
+    match COMMENT_MARKERS
+        .iter()
+        .find(|marker| rest.starts_with(**marker))
+    {
+        Some(&"#") => !after_code && !rest[1..].starts_with(['[', '!']),
+        Some(&"--" | &";;") => !after_code,
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Length of the string or character literal starting `rest`, if any. A
+/// string left open runs to the end of the line.
+fn literal_len(rest: &str) -> Option<usize> {
+    if let Some(string) = rest.strip_prefix('"') {
+        let mut escaped = false;
+        let end = string.find(|c| {
+            let closes = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            closes
+        });
+
+        return Some(end.map_or(rest.len(), |end| end + 2));
+    }
+
+    // `'"'` or `'\''`, an apostrophe alone being text or a lifetime
+    let mut chars = rest.strip_prefix('\'')?.char_indices();
+    let (_, c) = chars.next()?;
+    let (close, _) = match c {
+        '\\' => chars.nth(1),
+        _ => chars.next(),
+    }
+    .filter(|(_, c)| *c == '\'')?;
+
+    Some(close + 2)
+}
+
+/// Length of the block comment starting `rest`, if it is closed on the line
+fn closed_block_len(rest: &str) -> Option<usize> {
+    [("/*", "*/"), ("<!--", "-->")]
+        .iter()
+        .find_map(|(open, close)| {
+            let end = rest.strip_prefix(open)?.find(close)?;
+            Some(open.len() + end + close.len())
+        })
+}
+
+/// Whether the end of `line_prefix`, i.e. the text before the cursor, lies
+/// inside a comment
+pub fn is_in_comment(line_prefix: &str) -> bool {
+    comment_start(line_prefix).is_some()
+}
+
+/// Comment opened on the row of a keyword, which the following rows continue
+/// when they start with the same comment marker, at the same place, and are
+/// indented deeper than the keyword after it:
+///
+/// ```text
+/// // TODO: the first line of the comment
+/// //   continues on this one
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentBlock {
+    /// Text of the keyword row up to the end of the comment marker
+    lead: String,
+    /// Width of the whitespace between the marker and the keyword
+    indent: usize,
+}
+
+impl CommentBlock {
+    /// The block opened by the keyword at `column` of `line`, if the keyword
+    /// lies in a comment that only whitespace precedes
+    pub fn open(line: &str, column: usize) -> Option<Self> {
+        let start = comment_start(line.get(..column)?)?;
+        if !line[..start].trim().is_empty() {
+            return None;
+        }
+
+        let rest = &line[start..];
+        let marker = COMMENT_MARKERS
+            .iter()
+            .find(|marker| rest.starts_with(**marker))
+            .map_or(1, |marker| marker.len());
+        let lead_end = start + marker;
+        let after_marker = &line[lead_end..column];
+
+        Some(Self {
+            lead: line[..lead_end].to_owned(),
+            indent: after_marker.len() - after_marker.trim_start().len(),
+        })
+    }
+
+    /// Whether `line` continues the comment
+    pub fn continues(&self, line: &str) -> bool {
+        let Some(rest) = line.strip_prefix(&self.lead) else {
+            return false;
+        };
+        let content = rest.trim_start();
+
+        !content.trim_end().is_empty() && rest.len() - content.len() > self.indent
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comment_markers_are_detected() {
+        assert_eq!(comment_start("let a = 1; // TODO"), Some(11));
+        assert_eq!(comment_start("    # TODO"), Some(4));
+        assert_eq!(comment_start("-- TODO"), Some(0));
+        assert_eq!(comment_start("  <!-- "), Some(2));
+        assert_eq!(comment_start("   * "), Some(3));
+        assert_eq!(comment_start("a /* b */ // "), Some(10));
+        assert_eq!(comment_start("a /* b "), Some(2));
+    }
+
+    #[test]
+    fn markers_in_code_before_a_comment_are_skipped() {
+        assert_eq!(comment_start(r#"let u = "https://x"; // "#), Some(21));
+        assert_eq!(comment_start("i--; // "), Some(5));
+        assert_eq!(comment_start("#[cfg(test)] // "), Some(13));
+        assert_eq!(comment_start("*ptr = 1; // "), Some(10));
+        assert_eq!(comment_start("let c = '\"'; // "), Some(13));
+        assert_eq!(comment_start("let q = '\\''; // "), Some(14));
+    }
+
+    #[test]
+    fn markers_in_the_comment_text_are_skipped() {
+        assert_eq!(comment_start("// see #12 "), Some(0));
+        assert_eq!(comment_start("# step #2 "), Some(0));
+        assert_eq!(comment_start("    -- i-- is not a # comment "), Some(4));
+    }
+
+    #[test]
+    fn deeper_indented_rows_continue_a_comment() {
+        let block = CommentBlock::open("    // TODO: parse the", 7).unwrap();
+
+        assert!(block.continues("    //   arguments\n"));
+        assert!(block.continues("    //    arguments"));
+        assert!(!block.continues("    // arguments"));
+        assert!(!block.continues("    //  \n"));
+        assert!(!block.continues("  //   arguments"));
+        assert!(!block.continues("    #   arguments"));
+    }
+
+    #[test]
+    fn only_whole_line_comments_open_a_block() {
+        assert_eq!(CommentBlock::open("let a = 1; // TODO", 14), None);
+        assert_eq!(
+            CommentBlock::open("// see #12 TODO", 11),
+            Some(CommentBlock {
+                lead: "//".to_owned(),
+                indent: 1,
+            })
+        );
+        assert_eq!(
+            CommentBlock::open("   * TODO", 5),
+            Some(CommentBlock {
+                lead: "   *".to_owned(),
+                indent: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn code_is_not_a_comment() {
+        assert!(!is_in_comment("let a = b * c;"));
+        assert!(!is_in_comment("    */"));
+        assert!(!is_in_comment(""));
+        assert!(!is_in_comment("#[derive(Debug)] struct A; TO"));
+        assert!(!is_in_comment("/* done */ let x = TO"));
+        assert!(!is_in_comment("<!-- done --> <p>TO"));
+        assert!(!is_in_comment("x--; TO"));
+        assert!(!is_in_comment(r#"let u = "// TO"#));
+        assert!(is_in_comment("foo(); // TO"));
+    }
+}
+// grcov-excl-stop
@@ -0,0 +1,71 @@
+/// Scores how well `query` fuzzy matches `candidate`.
+///
+/// Every non-whitespace character of the query has to appear in the candidate,
+/// in order and case insensitively, otherwise there is no match. Consecutive
+/// characters and characters starting a word are rewarded, so a higher score
+/// means a better match. An empty query matches everything with a score of 0.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut candidate_chars = candidate.chars().flat_map(char::to_lowercase).enumerate();
+    let mut previous: Option<(usize, char)> = None;
+    let mut last_match: Option<usize> = None;
+
+    for query_char in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        loop {
+            let (i, c) = candidate_chars.next()?;
+            let word_start = previous.is_none_or(|(_, p)| !p.is_alphanumeric());
+            previous = Some((i, c));
+
+            if c != query_char {
+                continue;
+            }
+
+            score += 1;
+            if word_start {
+                score += 3;
+            }
+            if last_match.is_some_and(|last| last + 1 == i) {
+                score += 2;
+            }
+            last_match = Some(i);
+            break;
+        }
+    }
+
+    Some(score)
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(fuzzy_score("", "TODO: anything"), Some(0));
+        assert_eq!(fuzzy_score("   ", ""), Some(0));
+    }
+
+    #[test]
+    fn query_must_be_a_subsequence() {
+        assert!(fuzzy_score("fixme parser", "FIXME: the parser is slow").is_some());
+        assert!(fuzzy_score("fxprs", "FIXME: the parser is slow").is_some());
+        assert!(fuzzy_score("parser fixme", "FIXME: the parser is slow").is_none());
+        assert!(fuzzy_score("bug", "TODO: something").is_none());
+    }
+
+    #[test]
+    fn contiguous_and_word_start_matches_rank_higher() {
+        let exact = fuzzy_score("fixme parser", "FIXME: the parser is slow").unwrap();
+        let scattered =
+            fuzzy_score("fixme parser", "FIXME: pass the argument to the user").unwrap_or(0);
+
+        assert!(exact > scattered);
+        assert!(fuzzy_score("todo", "TODO").unwrap() > fuzzy_score("todo", "xtxoxdxo").unwrap());
+    }
+}
+// grcov-excl-stop
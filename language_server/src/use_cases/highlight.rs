@@ -28,6 +28,10 @@ impl<T: Colorer> Highlight<T> {
         self.inner.update_palette(key, value);
     }
 
+    pub fn clear_palette(&mut self) {
+        self.inner.clear_palette();
+    }
+
     pub fn color_intervals(
         &self,
         row_match_beginnings: &[Match],
@@ -1,6 +1,14 @@
+pub mod comment;
+pub mod fuzzy;
 pub mod highlight;
 pub mod ports;
+pub mod query;
 pub mod search;
+pub mod snippet;
 
+pub use comment::*;
+pub use fuzzy::*;
 pub use highlight::*;
+pub use query::*;
 pub use search::*;
+pub use snippet::*;
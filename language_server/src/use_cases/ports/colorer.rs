@@ -9,6 +9,8 @@ pub trait Colorer {
 
     fn update_palette(&mut self, text: String, colors: Colors);
 
+    fn clear_palette(&mut self);
+
     fn color_intervals(
         &self,
         row_matches: &[Match],
@@ -26,7 +28,7 @@ pub trait Colorer {
         let last_match = row_matches
                 .last()
                 .expect("This is always called when there is a match in user's text so there is something in the slice");
-        let line_end = (row_meta.line_len() - 1).into();
+        let line_end = (*row_meta.content_len()).into();
         color_intervals.push((*last_match.column(), line_end));
 
         // Extend the first match interval to include the text up until the
@@ -52,37 +54,39 @@ mod tests {
                fn color_text(&self, text: &str, color_type: ColorType) -> Option<Color>;
 
                fn update_palette(&mut self, text: String, colors: Colors);
+
+               fn clear_palette(&mut self);
            }
     }
 
     #[test]
     fn one_match_one_interval() {
-        let row_matches = vec![Match::new(3.into(), "keyword1".to_owned())];
-        let line_len = 20;
-        let row_meta = RowMetadata::new(line_len, 0.into());
+        let row_matches = vec![Match::new(3.into(), "keyword1".to_owned(), "")];
+        let content_len = 20;
+        let row_meta = RowMetadata::new(content_len, 0.into());
 
         assert_eq!(
             MockHighlighter::new().color_intervals(&row_matches, &row_meta),
-            vec![(0.into(), (line_len - 1).into())]
+            vec![(0.into(), content_len.into())]
         );
     }
 
     #[test]
     fn color_intervals_are_crrectly_deteted() {
         let row_matches = vec![
-            Match::new(3.into(), "keyword1".to_owned()),
-            Match::new(18.into(), "another-key".to_owned()),
-            Match::new(34.into(), "aword".to_owned()),
+            Match::new(3.into(), "keyword1".to_owned(), ""),
+            Match::new(18.into(), "another-key".to_owned(), ""),
+            Match::new(34.into(), "aword".to_owned(), ""),
         ];
-        let line_len = 80;
-        let row_meta = RowMetadata::new(line_len, 0.into());
+        let content_len = 80;
+        let row_meta = RowMetadata::new(content_len, 0.into());
 
         assert_eq!(
             MockHighlighter::new().color_intervals(&row_matches, &row_meta),
             vec![
                 (0.into(), 18.into()),
                 (18.into(), 34.into()),
-                (34.into(), (line_len - 1).into())
+                (34.into(), content_len.into())
             ]
         );
     }
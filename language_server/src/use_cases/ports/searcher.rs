@@ -1,7 +1,7 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use crate::{
-    entities::{FileState, State},
+    entities::{FileState, Match, Row, RowMetadata, State},
     use_cases::ports::RegexManager,
 };
 
@@ -10,6 +10,17 @@ pub trait Searcher {
 
     fn search_in_text(&self, text: &str) -> Option<FileState>;
 
+    /// Searches the `count` first of consecutive lines, the first one being at
+    /// `first_row`, and returns the rows holding matches. The lines after
+    /// them are only read as long as they continue the comment of the last
+    /// match.
+    fn search_in_lines<'a>(
+        &self,
+        first_row: Row,
+        count: usize,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> BTreeMap<Row, (RowMetadata, Vec<Match>)>;
+
     fn recurssive_search<P: AsRef<Path>>(&self, root: P) -> State;
 
     fn should_skip<P: AsRef<Path>>(&self, path: P) -> bool {
@@ -0,0 +1,103 @@
+use crate::entities::Match;
+
+/// Criteria a match has to meet to be listed by a query. Criteria left empty
+/// accept every match.
+#[derive(Debug, Default, Clone)]
+pub struct MatchFilter {
+    /// Keywords to keep
+    pub keywords: Vec<String>,
+    pub author: Option<String>,
+    pub ticket: Option<String>,
+    /// First date to keep, in the `YYYY-MM-DD` format
+    pub since: Option<String>,
+    /// Last date to keep, in the `YYYY-MM-DD` format
+    pub until: Option<String>,
+    /// Text the match has to contain, case insensitively
+    pub text: Option<String>,
+}
+
+impl MatchFilter {
+    pub fn accepts(&self, m: &Match) -> bool {
+        let annotation = m.annotation();
+        let date = annotation.date().as_deref();
+        // Dates in the `YYYY-MM-DD` format sort as strings
+        let in_range = |bound: &Option<String>, in_bound: fn(&str, &str) -> bool| {
+            bound
+                .as_deref()
+                .is_none_or(|bound| date.is_some_and(|date| in_bound(date, bound)))
+        };
+
+        (self.keywords.is_empty() || self.keywords.contains(m.keyword()))
+            && self
+                .author
+                .as_ref()
+                .is_none_or(|author| annotation.author().as_ref() == Some(author))
+            && self
+                .ticket
+                .as_ref()
+                .is_none_or(|ticket| annotation.ticket().as_ref() == Some(ticket))
+            && in_range(&self.since, |date, since| date >= since)
+            && in_range(&self.until, |date, until| date <= until)
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|text| m.summary().to_lowercase().contains(&text.to_lowercase()))
+    }
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(keyword: &str, segment: &str) -> Match {
+        Match::new(0.into(), keyword.to_owned(), segment)
+    }
+
+    #[test]
+    fn empty_filter_accepts_everything() {
+        assert!(MatchFilter::default().accepts(&m("TODO", "")));
+    }
+
+    #[test]
+    fn matches_are_filtered_by_keyword_and_annotation() {
+        let filter = MatchFilter {
+            keywords: vec!["FIXME".to_owned()],
+            author: Some("alice".to_owned()),
+            ticket: Some("#12".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(&m("FIXME", "(alice, #12): parse")));
+        assert!(!filter.accepts(&m("TODO", "(alice, #12): parse")));
+        assert!(!filter.accepts(&m("FIXME", "(bob, #12): parse")));
+        assert!(!filter.accepts(&m("FIXME", "(alice): parse")));
+    }
+
+    #[test]
+    fn dates_are_kept_within_the_range() {
+        let filter = MatchFilter {
+            since: Some("2026-01-01".to_owned()),
+            until: Some("2026-06-30".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(&m("TODO", "(2026-01-01)")));
+        assert!(filter.accepts(&m("TODO", "(2026-06-30)")));
+        assert!(!filter.accepts(&m("TODO", "(2025-12-31)")));
+        assert!(!filter.accepts(&m("TODO", "(2026-07-01)")));
+        assert!(!filter.accepts(&m("TODO", ": no date")));
+    }
+
+    #[test]
+    fn text_is_searched_case_insensitively() {
+        let filter = MatchFilter {
+            text: Some("todo: PARSE".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(&m("TODO", ": parse the args")));
+        assert!(!filter.accepts(&m("TODO", ": lex the args")));
+    }
+}
+// grcov-excl-stop
@@ -1,12 +1,13 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use crate::{
-    entities::{FileState, State, TodoResult},
+    entities::{FileState, Match, Row, RowMetadata, State, TodoResult},
     use_cases::ports::RegexManager,
 };
 
 use super::ports::Searcher;
 
+#[derive(Clone)]
 pub struct Search<T> {
     inner: T,
 }
@@ -26,9 +27,22 @@ impl<T: Searcher> Search<T> {
         self.inner.search_in_text(text)
     }
 
+    pub fn search_in_lines<'a>(
+        &self,
+        first_row: Row,
+        count: usize,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> BTreeMap<Row, (RowMetadata, Vec<Match>)> {
+        self.inner.search_in_lines(first_row, count, lines)
+    }
+
     pub fn recurssive_search<P: AsRef<Path>>(&self, root: P) -> State {
         self.inner.recurssive_search(root)
     }
+
+    pub fn should_skip<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.inner.should_skip(path)
+    }
 }
 
 impl<T: RegexManager> Search<T> {
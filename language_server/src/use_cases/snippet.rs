@@ -0,0 +1,79 @@
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Snippet inserted for `keyword` when the user did not configure a template
+pub fn default_template(keyword: &str) -> String {
+    format!("{keyword}(${{1:$USER}}): $0")
+}
+
+/// Replaces the `$USER` and `$DATE` variables of a snippet template, every
+/// other snippet construct being left to the editor
+pub fn expand_template(template: &str, user: &str, date: &str) -> String {
+    template.replace("$USER", user).replace("$DATE", date)
+}
+
+/// Name used for the `$USER` variable of the templates and for the author of
+/// the annotations
+pub fn user_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "author".to_owned())
+}
+
+/// Today's date in the `YYYY-MM-DD` format
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    civil_date(seconds / 86_400)
+}
+
+/// Converts a number of days since 1970-01-01 into a `YYYY-MM-DD` date
+fn civil_date(days: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// grcov-excl-start
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_template_asks_for_the_author() {
+        assert_eq!(default_template("TODO"), "TODO(${1:$USER}): $0");
+    }
+
+    #[test]
+    fn variables_are_expanded() {
+        assert_eq!(
+            expand_template("TODO(${1:$USER}, $DATE): $0", "alice", "2026-10-18"),
+            "TODO(${1:alice}, 2026-10-18): $0"
+        );
+        assert_eq!(
+            expand_template("FIXME(#${1:ticket}): $0", "alice", "2026-10-18"),
+            "FIXME(#${1:ticket}): $0"
+        );
+    }
+
+    #[test]
+    fn days_are_converted_to_dates() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(20_744), "2026-10-18");
+    }
+}
+// grcov-excl-stop